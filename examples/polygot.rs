@@ -30,8 +30,8 @@ fn main() {
         Language::Swift(Default::default()),
     ];
 
-    for lang in languages {
+    for (lang, code) in languages.iter().zip(gen.finish_many(languages)) {
         eprintln!("--- {}", lang.name());
-        eprintln!("{}", gen.finish(lang.clone()));
+        eprintln!("{}", code.unwrap());
     }
 }
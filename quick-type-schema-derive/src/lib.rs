@@ -0,0 +1,66 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, LitStr};
+
+/// Register a type deriving `JsonSchema` for `CodegenContext::add_all_registered`.
+/// Groups are assigned with `#[quick_type_schema(group = "api")]`, which may be repeated.
+/// Types deriving schemars 1.x `JsonSchema` are marked with `#[quick_type_schema(v1)]`.
+#[proc_macro_derive(QuickTypeExport, attributes(quick_type_schema))]
+pub fn derive_quick_type_export(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "generic types can't be registered, add each instantiation with `add_type` instead",
+        ));
+    }
+
+    let mut groups = vec![];
+    let mut v1 = false;
+    for attr in &input.attrs {
+        if !attr.path().is_ident("quick_type_schema") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("group") {
+                groups.push(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else if meta.path.is_ident("v1") {
+                v1 = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `group = \"...\"` or `v1`"))
+            }
+        })?;
+    }
+
+    let ident = &input.ident;
+    let add_type = if v1 {
+        quote! { try_add_type_v1 }
+    } else {
+        quote! { try_add_type }
+    };
+    Ok(quote! {
+        const _: () = {
+            fn register(
+                ctx: &mut ::quick_type_schema::CodegenContext,
+            ) -> ::core::result::Result<(), ::quick_type_schema::SchemaError> {
+                ctx.#add_type::<#ident>()
+            }
+
+            ::quick_type_schema::inventory::submit! {
+                ::quick_type_schema::RegisteredType {
+                    name: ::core::concat!(::core::module_path!(), "::", ::core::stringify!(#ident)),
+                    groups: &[#(#groups),*],
+                    register,
+                }
+            }
+        };
+    })
+}
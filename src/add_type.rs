@@ -0,0 +1,186 @@
+use super::*;
+
+#[cfg(feature = "schemars08")]
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema_for, JsonSchema,
+};
+
+/// Builds the schemars 0.8 settings of `add_type`, see `CodegenContext::with_schema_settings`.
+#[cfg(feature = "schemars08")]
+#[derive(Clone)]
+pub(crate) struct Settings(Arc<dyn Fn() -> SchemaSettings + Send + Sync>);
+
+#[cfg(feature = "schemars08")]
+impl std::fmt::Debug for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Settings")
+    }
+}
+
+#[cfg(feature = "schemars08")]
+impl CodegenContext {
+    /// Generate the schemas of `add_type` with the settings returned by `settings` instead of schemars' draft-07 defaults.
+    /// A generator is built from `settings` for every added type, since schemars 0.8 generators can't be shared across threads.
+    /// Definitions shared by several types are still only emitted once, since they are deduplicated when schemas are merged.
+    pub fn with_schema_settings(
+        mut self,
+        settings: impl Fn() -> SchemaSettings + Send + Sync + 'static,
+    ) -> Self {
+        self.schema_settings = Some(Settings(Arc::new(settings)));
+        self
+    }
+
+    pub fn add_type<T: JsonSchema>(&mut self) {
+        self.try_add_type::<T>().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_add_type<T: JsonSchema>(&mut self) -> Result<(), SchemaError> {
+        let (schema, definitions_path) = match &self.schema_settings {
+            Some(Settings(settings)) => {
+                let mut generator = SchemaGenerator::new(settings());
+                (
+                    generator.root_schema_for::<T>(),
+                    Some(generator.settings().definitions_path.clone()),
+                )
+            }
+            None => (schema_for!(T), None),
+        };
+        self.schema.push_schema(
+            &serde_json::to_string(&schema).unwrap(),
+            None,
+            Some(std::any::type_name::<T>()),
+            definitions_path.as_deref(),
+        )
+    }
+}
+
+#[cfg(feature = "schemars1")]
+impl CodegenContext {
+    /// Like `with_schema_settings`, for `add_type_v1`. Transforms are supported.
+    pub fn with_schema_settings_v1(
+        mut self,
+        settings: schemars1::generate::SchemaSettings,
+    ) -> Self {
+        self.generator_v1 = Some(Arc::new(Mutex::new(settings.into_generator())));
+        self
+    }
+
+    /// Like `add_type`, for types deriving `JsonSchema` from schemars 1.x.
+    pub fn add_type_v1<T: schemars1::JsonSchema>(&mut self) {
+        self.try_add_type_v1::<T>()
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_add_type_v1<T: schemars1::JsonSchema>(&mut self) -> Result<(), SchemaError> {
+        let (schema, definitions_path) = match &self.generator_v1 {
+            Some(generator) => {
+                let mut generator = generator.lock().unwrap();
+                // Unlike 0.8, the definitions path is a json pointer such as `/$defs`.
+                let definitions_path = format!(
+                    "#{}/",
+                    generator.settings().definitions_path.trim_end_matches('/')
+                );
+                (generator.root_schema_for::<T>(), Some(definitions_path))
+            }
+            None => (schemars1::schema_for!(T), None),
+        };
+        self.schema.push_schema(
+            &serde_json::to_string(&schema).unwrap(),
+            None,
+            Some(std::any::type_name::<T>()),
+            definitions_path.as_deref(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "schemars08")]
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct A {
+        x: i32,
+    }
+
+    #[cfg(feature = "schemars08")]
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct B {
+        /// Described, so `RemoveRefSiblings` changes the schema
+        a: A,
+    }
+
+    #[cfg(feature = "schemars08")]
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct C {
+        x: Option<i32>,
+    }
+
+    #[cfg(feature = "schemars08")]
+    #[test]
+    fn nested_and_root() {
+        let mut ctx = CodegenContext::new("T", None);
+        ctx.try_add_type::<A>().unwrap();
+        ctx.try_add_type::<B>().unwrap();
+
+        let mut ctx = CodegenContext::new("T", None);
+        ctx.try_add_type::<B>().unwrap();
+        ctx.try_add_type::<A>().unwrap();
+    }
+
+    #[cfg(feature = "schemars08")]
+    #[test]
+    fn schema_settings() {
+        let mut ctx = CodegenContext::new("T", None).with_schema_settings(SchemaSettings::openapi3);
+        ctx.try_add_type::<B>().unwrap();
+        ctx.try_add_type::<A>().unwrap();
+        ctx.try_add_type::<C>().unwrap();
+
+        let schema = ctx.schema.to_json();
+        assert!(schema.contains(r#""nullable":true"#), "{}", schema);
+        // Added by the `RemoveRefSiblings` visitor
+        assert!(schema.contains(r#""allOf""#), "{}", schema);
+    }
+
+    #[cfg(feature = "schemars1")]
+    #[allow(dead_code)]
+    #[derive(schemars1::JsonSchema)]
+    #[schemars(crate = "schemars1")]
+    struct A1 {
+        x: i32,
+    }
+
+    #[cfg(feature = "schemars1")]
+    #[allow(dead_code)]
+    #[derive(schemars1::JsonSchema)]
+    #[schemars(crate = "schemars1")]
+    struct B1 {
+        a: A1,
+    }
+
+    #[cfg(feature = "schemars1")]
+    #[test]
+    fn nested_and_root_v1() {
+        let mut ctx = CodegenContext::new("T", None);
+        ctx.try_add_type_v1::<A1>().unwrap();
+        ctx.try_add_type_v1::<B1>().unwrap();
+
+        let mut ctx = CodegenContext::new("T", None);
+        ctx.try_add_type_v1::<B1>().unwrap();
+        ctx.try_add_type_v1::<A1>().unwrap();
+    }
+
+    #[cfg(feature = "schemars1")]
+    #[test]
+    fn shared_generator_v1() {
+        let mut ctx = CodegenContext::new("T", None)
+            .with_schema_settings_v1(schemars1::generate::SchemaSettings::draft07());
+        ctx.try_add_type_v1::<B1>().unwrap();
+        ctx.try_add_type_v1::<A1>().unwrap();
+        ctx.try_add_type_v1::<B1>().unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use super::*;
+use finish::{check_output, read_files, single_file, Files, Job};
+use std::process::{Output, Stdio};
+use std::time::Instant;
+
+impl CodegenContext {
+    /// `try_finish` for async runtimes.
+    /// quicktype runs through `tokio::process`, other blocking work (runner discovery, the `QuicktypeServer`, writing the schema) runs on the blocking thread pool.
+    pub async fn finish_async(&self, lang: Language) -> Result<String, CodegenError> {
+        let ctx = self.clone();
+        let job_lang = lang.clone();
+        let (args, cache_key) = match blocking(move || {
+            let job = ctx.prepare(&job_lang)?;
+            if let Job::Run { .. } = job {
+                ctx.resolve_runner()?;
+            }
+            Ok(job)
+        })
+        .await?
+        {
+            Job::Done(files, version) => {
+                return self.postprocess_async(&lang, files, version).await
+            }
+            Job::Run { args, cache_key } => (args, cache_key),
+        };
+
+        // `resolved_runner` is shared with `ctx`, so this doesn't block.
+        let runner = self.resolve_runner()?;
+        let dir = TempDir::new()?;
+        let ctx = self.clone();
+        let path = dir.path().to_owned();
+        let schema_path = blocking(move || ctx.write_schema(&path)).await?;
+        let out_dir = dir.path().join("out");
+        tokio::fs::create_dir(&out_dir).await?;
+        let out_path = out_dir.join(self.file_name(&lang));
+
+        let cmd = self.quicktype_command(runner, &args, &schema_path, &out_path);
+        let output = output_async(cmd, self.limits()).await?;
+        check_output(output, &out_dir)?;
+        let files = read_files(&out_dir)?;
+
+        let ctx = self.clone();
+        let version = blocking(move || Ok(ctx.generated_version())).await?;
+        self.cache_put(cache_key.as_deref(), &files, version.as_deref());
+        self.postprocess_async(&lang, files, version).await
+    }
+
+    async fn postprocess_async(
+        &self,
+        lang: &Language,
+        files: Files,
+        version: Option<String>,
+    ) -> Result<String, CodegenError> {
+        let ctx = self.clone();
+        let lang = lang.clone();
+        blocking(move || ctx.postprocess(&lang, files, version.as_deref(), &mut vec![]))
+            .await
+            .and_then(single_file)
+    }
+}
+
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, CodegenError> + Send + 'static,
+) -> Result<T, CodegenError> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(res) => res,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(CodegenError::Cancelled),
+    }
+}
+
+/// `exec::output` for tokio.
+async fn output_async(
+    cmd: std::process::Command,
+    limits: Limits<'_>,
+) -> Result<Output, CodegenError> {
+    if limits.cancel.is_cancelled() {
+        return Err(CodegenError::Cancelled);
+    }
+
+    let mut cmd = cmd;
+    exec::own_process_group(&mut cmd);
+    let mut cmd = tokio::process::Command::from(cmd);
+    let child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // Dropping `wait` drops and kills the child, the rest of its process group is killed explicitly.
+    let start = Instant::now();
+    let pid = child.id();
+    let kill_group = || {
+        if let Some(pid) = pid {
+            exec::kill_process_group(pid);
+        }
+    };
+    let wait = child.wait_with_output();
+    tokio::pin!(wait);
+    loop {
+        tokio::select! {
+            output = &mut wait => return Ok(output?),
+            _ = tokio::time::sleep(exec::POLL_INTERVAL) => {
+                if limits.cancel.is_cancelled() {
+                    kill_group();
+                    return Err(CodegenError::Cancelled);
+                }
+                if let Some(timeout) = limits.timeout.filter(|t| start.elapsed() >= *t) {
+                    kill_group();
+                    return Err(CodegenError::Timeout(timeout));
+                }
+            }
+        }
+    }
+}
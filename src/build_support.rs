@@ -0,0 +1,171 @@
+//! Generate bindings from a `build.rs`.
+//!
+//! ```ignore
+//! // build.rs
+//! use quick_type_schema::{build_support::BuildCodegen, CodegenContext, Language};
+//!
+//! let mut build = BuildCodegen::new(CodegenContext::new("Api", None));
+//! build.add_schema_file("schemas/api.json");
+//! build.generate(Language::Rust(Default::default()), "api.rs");
+//!
+//! // lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/api.rs"));
+//! ```
+
+use super::*;
+use std::ffi::OsStr;
+
+/// Wraps a `CodegenContext` for build scripts.
+#[derive(Debug, Clone)]
+pub struct BuildCodegen {
+    ctx: CodegenContext,
+    out_dir: Option<PathBuf>,
+}
+
+impl BuildCodegen {
+    /// Tells cargo to rerun the build script when the environment variables that override the runner change.
+    pub fn new(ctx: CodegenContext) -> Self {
+        let build = BuildCodegen { ctx, out_dir: None };
+        for var in build.runner_vars() {
+            println!("cargo:rerun-if-env-changed={}", var);
+        }
+        build
+    }
+
+    /// Write generated files to `out_dir` instead of `$OUT_DIR`.
+    pub fn with_out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    pub fn context(&self) -> &CodegenContext {
+        &self.ctx
+    }
+
+    /// For adding types, which should also be passed to `rerun_if_changed`.
+    pub fn context_mut(&mut self) -> &mut CodegenContext {
+        &mut self.ctx
+    }
+
+    /// Tell cargo to rerun the build script when `path` changes.
+    pub fn rerun_if_changed(&self, path: impl AsRef<Path>) {
+        println!("cargo:rerun-if-changed={}", path.as_ref().display());
+    }
+
+    /// Add a json schema file, named after the file when it has no `title`.
+    /// Panics if the file can't be read or isn't a valid schema, failing the build.
+    pub fn add_schema_file(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        self.rerun_if_changed(path);
+        let schema = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e));
+        let title = path.file_stem().and_then(OsStr::to_str).unwrap_or_default();
+        self.ctx
+            .try_add_schema_with_title(&schema, title)
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    }
+
+    /// Write generated code to `file_name` in the output directory and return its path.
+    /// quicktype is skipped if nothing changed since the last successful run.
+    /// Failures are printed as cargo warnings and leave any previously generated file in place,
+    /// in which case `None` is returned.
+    pub fn generate(&self, lang: Language, file_name: impl AsRef<Path>) -> Option<PathBuf> {
+        let path = self.out_dir()?.join(file_name);
+        let stamp_path = self.stamp_path(&path)?;
+        let stamp = self.stamp(&lang);
+        let fresh = path.exists()
+            && std::fs::read_to_string(&stamp_path).is_ok_and(|existing| existing == stamp);
+        if fresh {
+            return Some(path);
+        }
+
+        let res = self
+            .ctx
+            .finish_to_path(lang, &path)
+            .and_then(|_| Ok(write::write_if_changed(&stamp_path, &stamp)?));
+        match res {
+            Ok(_) => Some(path),
+            Err(e) => {
+                warn(&format!("failed to generate {}: {}", path.display(), e));
+                None
+            }
+        }
+    }
+
+    fn out_dir(&self) -> Option<PathBuf> {
+        match &self.out_dir {
+            Some(out_dir) => Some(out_dir.clone()),
+            None => match std::env::var_os("OUT_DIR") {
+                Some(out_dir) => Some(out_dir.into()),
+                None => {
+                    warn("OUT_DIR is not set, use `with_out_dir` outside of build scripts");
+                    None
+                }
+            },
+        }
+    }
+
+    /// Stamps are kept in `$OUT_DIR` so they don't end up next to checked in files.
+    fn stamp_path(&self, path: &Path) -> Option<PathBuf> {
+        let dir = std::env::var_os("OUT_DIR")
+            .map(PathBuf::from)
+            .or_else(|| Some(path.parent()?.to_owned()))?;
+        let name = hash::hash_hex(&[path.to_string_lossy().as_bytes()]);
+        Some(dir.join(format!("quick-type-schema-{}.stamp", name)))
+    }
+
+    /// `$PATH` is left out since it tends to differ between editors and terminals,
+    /// changes to the quicktype it finds are caught by `Runner::fingerprint` instead.
+    fn runner_vars(&self) -> Vec<&str> {
+        let mut vars = vec![QUICKTYPE_BIN_ENV, "NODE_PATH"];
+        if let Runner::Env(var) = &self.ctx.runner {
+            vars.push(var);
+        }
+        vars
+    }
+
+    /// Everything that affects the output and is known without running quicktype.
+    /// `Formatter::Custom` closures can't be compared.
+    fn stamp(&self, lang: &Language) -> String {
+        let mut parts = vec![
+            env!("CARGO_PKG_VERSION").to_owned(),
+            self.ctx.base_name.clone(),
+            format!("{:?} {:?}", self.ctx.header, self.ctx.formatter),
+        ];
+        for (path, contents) in self.ctx.schema_files().0 {
+            parts.push(path.to_string_lossy().into_owned());
+            parts.push(contents);
+        }
+        parts.extend(self.ctx.quicktype_args(lang));
+        parts.push(self.ctx.runner.fingerprint());
+        for var in self.runner_vars() {
+            parts.push(std::env::var(var).unwrap_or_default());
+        }
+        hash::hash_hex(&parts.iter().map(|part| part.as_bytes()).collect::<Vec<_>>())
+    }
+}
+
+fn warn(message: &str) {
+    print!("{}", cargo_warning(message));
+}
+
+/// Multi-line messages need every line prefixed to be shown.
+fn cargo_warning(message: &str) -> String {
+    message
+        .lines()
+        .map(|line| format!("cargo:warning={}\n", line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_line_warning() {
+        assert_eq!(
+            cargo_warning("failed to generate api.rs: quicktype failed\n  error: bad"),
+            "cargo:warning=failed to generate api.rs: quicktype failed\ncargo:warning=  error: bad\n"
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use super::*;
+use finish::Files;
+use json::JsonValue;
+
+/// Bump when the format of cache entries changes.
+const FORMAT: &str = "files-v2";
+
+/// On-disk cache of quicktype output keyed by a hash of everything that affects it.
+#[derive(Debug, Clone)]
+pub(crate) struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Cache { dir }
+    }
+
+    /// `quicktype` identifies the quicktype that would run without running it.
+    pub fn key(schema: &str, base_name: &str, args: &[String], quicktype: &str) -> String {
+        let mut parts = vec![
+            env!("CARGO_PKG_VERSION").as_bytes(),
+            FORMAT.as_bytes(),
+            schema.as_bytes(),
+            base_name.as_bytes(),
+            quicktype.as_bytes(),
+        ];
+        parts.extend(args.iter().map(|arg| arg.as_bytes()));
+        hash::hash_hex(&parts)
+    }
+
+    /// Entries are a json object with `files`, an object of relative paths to file contents,
+    /// and the `version` of quicktype that generated them if it was known.
+    /// Anything else is a miss.
+    pub fn get(&self, key: &str) -> Option<(Files, Option<String>)> {
+        let entry = json::parse(&std::fs::read_to_string(self.dir.join(key)).ok()?).ok()?;
+        let files = &entry["files"];
+        if !files.is_object() || files.is_empty() {
+            return None;
+        }
+        let files = files
+            .entries()
+            .map(|(path, code)| Some((PathBuf::from(path), code.as_str()?.to_owned())))
+            .collect::<Option<Files>>()?;
+        Some((files, entry["version"].as_str().map(|v| v.to_owned())))
+    }
+
+    /// Failing to write to the cache is not an error, the next run simply misses.
+    pub fn put(&self, key: &str, files: &Files, version: Option<&str>) {
+        let mut entry = JsonValue::new_object();
+        for (path, code) in files {
+            entry["files"][path.to_string_lossy().as_ref()] = code.as_str().into();
+        }
+        if let Some(version) = version {
+            entry["version"] = version.into();
+        }
+        let _ = write::write_if_changed(&self.dir.join(key), &entry.dump());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use finish::Job;
+    use temp::TempDir;
+
+    #[test]
+    fn invalid_entries_miss() {
+        let dir = TempDir::new().unwrap();
+        let cache = Cache::new(dir.path().to_owned());
+        let invalid = [
+            "[]",
+            "{}",
+            "1",
+            "{",
+            r#"{ "files": {} }"#,
+            r#"{ "files": [] }"#,
+            r#"{ "a.ts": "code" }"#,
+        ];
+        for (i, entry) in invalid.into_iter().enumerate() {
+            let key = i.to_string();
+            std::fs::write(dir.path().join(&key), entry).unwrap();
+            assert_eq!(cache.get(&key), None, "{}", entry);
+        }
+
+        let files = Files::from([(PathBuf::from("a.ts"), "code".to_owned())]);
+        cache.put("valid", &files, Some("23.0.1"));
+        assert_eq!(
+            cache.get("valid"),
+            Some((files.clone(), Some("23.0.1".to_owned())))
+        );
+        cache.put("unversioned", &files, None);
+        assert_eq!(cache.get("unversioned"), Some((files, None)));
+    }
+
+    #[test]
+    fn hit_skips_quicktype() {
+        let dir = TempDir::new().unwrap();
+        let mut ctx = CodegenContext::new("TopLevel", None)
+            .with_runner(Runner::Path(dir.path().join("missing-quicktype")))
+            .with_cache_dir(dir.path().join("cache"))
+            .with_header();
+        ctx.add_schema(r#"{ "title": "A", "type": "string" }"#);
+        let lang = Language::Typescript(Default::default());
+
+        let Ok(Job::Run { cache_key, .. }) = ctx.prepare(&lang) else {
+            panic!("expected a cache miss");
+        };
+        let files = Files::from([(ctx.file_name(&lang), "cached".to_owned())]);
+        ctx.cache_put(
+            cache_key.as_deref(),
+            &files,
+            Some("quicktype version 23.0.1"),
+        );
+        let code = ctx.try_finish(lang).unwrap();
+        assert!(code.contains("with quicktype 23.0.1."), "{}", code);
+        assert!(code.ends_with("cached"), "{}", code);
+    }
+}
@@ -0,0 +1,31 @@
+use super::*;
+use similar::TextDiff;
+
+impl CodegenContext {
+    /// Verify that `path` contains exactly what `finish_to_path` would write.
+    /// Meant for tests and CI, so that committed bindings can't go stale.
+    pub fn check(&self, lang: Language, path: impl AsRef<Path>) -> Result<(), Drift> {
+        let path = path.as_ref();
+        let code = self.try_finish(lang).map_err(Drift::Codegen)?;
+        let existing = match std::fs::read_to_string(path) {
+            Ok(existing) => existing,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Drift::Missing(path.to_owned()))
+            }
+            Err(e) => return Err(Drift::Codegen(e.into())),
+        };
+        if existing == code {
+            return Ok(());
+        }
+
+        let path_display = path.display().to_string();
+        let diff = TextDiff::from_lines(&existing, &code)
+            .unified_diff()
+            .header(&path_display, &format!("{} (generated)", path_display))
+            .to_string();
+        Err(Drift::Changed {
+            path: path.to_owned(),
+            diff,
+        })
+    }
+}
@@ -0,0 +1,246 @@
+use super::*;
+use std::fmt;
+use std::process::ExitStatus;
+use std::string::FromUtf8Error;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum CodegenError {
+    /// No usable quicktype runner was found, see `Runner`
+    RunnerNotFound(DiscoveryReport),
+    /// quicktype exited unsuccessfully.
+    /// `stderr` only contains the lines that aren't warnings, unless all of them are.
+    QuicktypeFailed {
+        stderr: String,
+        exit_status: ExitStatus,
+    },
+    /// quicktype succeeded but did not write the expected output file
+    OutputMissing,
+    /// quicktype wrote several files, use `CodegenContext::finish_files` instead
+    MultipleFiles(Vec<PathBuf>),
+    Io(std::io::Error),
+    InvalidUtf8(FromUtf8Error),
+    /// quicktype did not finish within `CodegenContext::with_timeout`
+    Timeout(Duration),
+    /// `CancelHandle::cancel` was called
+    Cancelled,
+    /// `quicktype --version` printed something that isn't a version
+    UnknownVersion(String),
+    /// An option requires a newer quicktype than the one installed
+    UnsupportedOption {
+        option: &'static str,
+        required: QuicktypeVersion,
+        found: QuicktypeVersion,
+    },
+    /// The `QuicktypeServer` worker could not be started or has exited
+    WorkerUnavailable(String),
+    /// The `QuicktypeServer` worker reported an error
+    WorkerFailed(String),
+    /// The `Formatter` exited unsuccessfully
+    FormatterFailed {
+        formatter: String,
+        stderr: String,
+    },
+    /// `CodegenContext::with_converters` named types that weren't added
+    UnknownConverters(Vec<String>),
+}
+
+impl CodegenError {
+    /// `CodegenError` can't be `Clone` because of `std::io::Error`.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            CodegenError::RunnerNotFound(report) => CodegenError::RunnerNotFound(report.clone()),
+            CodegenError::QuicktypeFailed {
+                stderr,
+                exit_status,
+            } => CodegenError::QuicktypeFailed {
+                stderr: stderr.clone(),
+                exit_status: *exit_status,
+            },
+            CodegenError::OutputMissing => CodegenError::OutputMissing,
+            CodegenError::MultipleFiles(files) => CodegenError::MultipleFiles(files.clone()),
+            CodegenError::Io(e) => CodegenError::Io(std::io::Error::new(e.kind(), e.to_string())),
+            CodegenError::InvalidUtf8(e) => CodegenError::InvalidUtf8(e.clone()),
+            CodegenError::Timeout(timeout) => CodegenError::Timeout(*timeout),
+            CodegenError::Cancelled => CodegenError::Cancelled,
+            CodegenError::UnknownVersion(v) => CodegenError::UnknownVersion(v.clone()),
+            CodegenError::UnsupportedOption {
+                option,
+                required,
+                found,
+            } => CodegenError::UnsupportedOption {
+                option,
+                required: *required,
+                found: *found,
+            },
+            CodegenError::WorkerUnavailable(e) => CodegenError::WorkerUnavailable(e.clone()),
+            CodegenError::WorkerFailed(e) => CodegenError::WorkerFailed(e.clone()),
+            CodegenError::FormatterFailed { formatter, stderr } => CodegenError::FormatterFailed {
+                formatter: formatter.clone(),
+                stderr: stderr.clone(),
+            },
+            CodegenError::UnknownConverters(names) => {
+                CodegenError::UnknownConverters(names.clone())
+            }
+        }
+    }
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::RunnerNotFound(report) => {
+                write!(f, "could not find quicktype, tried:\n{}", report)
+            }
+            CodegenError::QuicktypeFailed {
+                stderr,
+                exit_status,
+            } => write!(f, "quicktype failed ({}): {}", exit_status, stderr),
+            CodegenError::OutputMissing => write!(f, "quicktype did not generate any output"),
+            CodegenError::MultipleFiles(files) => write!(
+                f,
+                "quicktype generated {} files, use `finish_files` instead",
+                files.len()
+            ),
+            CodegenError::Io(e) => write!(f, "io error: {}", e),
+            CodegenError::InvalidUtf8(e) => write!(f, "quicktype produced invalid utf-8: {}", e),
+            CodegenError::Timeout(timeout) => {
+                write!(f, "quicktype timed out after {:?}", timeout)
+            }
+            CodegenError::Cancelled => write!(f, "quicktype was cancelled"),
+            CodegenError::UnknownVersion(v) => {
+                write!(f, "could not parse quicktype version from {:?}", v)
+            }
+            CodegenError::UnsupportedOption {
+                option,
+                required,
+                found,
+            } => write!(
+                f,
+                "`{}` requires quicktype {} or newer, but {} is installed",
+                option, required, found
+            ),
+            CodegenError::WorkerUnavailable(e) => write!(f, "quicktype worker unavailable: {}", e),
+            CodegenError::WorkerFailed(e) => write!(f, "quicktype worker failed: {}", e),
+            CodegenError::FormatterFailed { formatter, stderr } => {
+                write!(f, "`{}` failed: {}", formatter, stderr)
+            }
+            CodegenError::UnknownConverters(names) => {
+                write!(f, "no added type is named {}", names.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CodegenError::Io(e) => Some(e),
+            CodegenError::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CodegenError {
+    fn from(e: std::io::Error) -> Self {
+        CodegenError::Io(e)
+    }
+}
+
+impl From<FromUtf8Error> for CodegenError {
+    fn from(e: FromUtf8Error) -> Self {
+        CodegenError::InvalidUtf8(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// The schema is not valid json.
+    /// `line` and `column` point to the end of the input when the exact position is unknown.
+    Parse {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// The root of the schema is not a json object
+    NotAnObject,
+    /// The schema has no string `title` and no fallback title was given
+    MissingTitle,
+    /// A definition with the same name but a different shape was already added
+    ConflictingDefinition { name: String },
+}
+
+impl SchemaError {
+    pub(crate) fn from_json(e: json::Error, src: &str) -> Self {
+        let (line, column) = match e {
+            json::Error::UnexpectedCharacter { line, column, .. } => (line, column),
+            _ => {
+                let line = src.lines().count().max(1);
+                let column = src.lines().last().map(|l| l.chars().count()).unwrap_or(0) + 1;
+                (line, column)
+            }
+        };
+        SchemaError::Parse {
+            message: e.to_string(),
+            line,
+            column,
+        }
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Parse {
+                message,
+                line,
+                column,
+            } => write!(f, "invalid schema at {}:{}: {}", line, column, message),
+            SchemaError::NotAnObject => write!(f, "schema root is not an object"),
+            SchemaError::MissingTitle => write!(f, "schema has no `title`"),
+            SchemaError::ConflictingDefinition { name } => write!(
+                f,
+                "conflicting definitions named `{}`, see `ConflictStrategy`",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Returned by `CodegenContext::check` when a file on disk doesn't match generated code.
+#[derive(Debug)]
+pub enum Drift {
+    /// The file does not exist
+    Missing(PathBuf),
+    /// The file is out of date. `diff` is a unified diff from the file to the generated code.
+    Changed { path: PathBuf, diff: String },
+    /// Code could not be generated to compare against
+    Codegen(CodegenError),
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::Missing(path) => write!(f, "{} does not exist", path.display()),
+            Drift::Changed { path, diff } => write!(
+                f,
+                "{} is out of date, regenerate it:\n{}",
+                path.display(),
+                diff
+            ),
+            Drift::Codegen(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Drift {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Drift::Codegen(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use super::*;
+use std::io::{Read, Write};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+/// Cancels running and future quicktype invocations of a `CodegenContext` (and its clones).
+/// Obtained through `CodegenContext::cancel_handle`.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Allow quicktype to run again after `cancel`.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Limits<'a> {
+    pub timeout: Option<Duration>,
+    pub cancel: &'a CancelHandle,
+}
+
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Like `Command::output`, but kills the child when `limits` are exceeded.
+pub(crate) fn output(cmd: &mut Command, limits: Limits) -> Result<Output, CodegenError> {
+    output_with_input(cmd, None, limits)
+}
+
+/// `output`, with `input` written to the child's stdin.
+pub(crate) fn output_with_input(
+    cmd: &mut Command,
+    input: Option<&str>,
+    limits: Limits,
+) -> Result<Output, CodegenError> {
+    if limits.cancel.is_cancelled() {
+        return Err(CodegenError::Cancelled);
+    }
+
+    own_process_group(cmd);
+    let mut child = cmd
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        let input = input.to_owned();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        let err = match limits.timeout {
+            _ if limits.cancel.is_cancelled() => Some(CodegenError::Cancelled),
+            Some(timeout) if start.elapsed() >= timeout => Some(CodegenError::Timeout(timeout)),
+            _ => None,
+        };
+        if let Some(err) = err {
+            kill_process_group(child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(err);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Start `cmd` in its own process group, so that `kill_process_group` also reaches
+/// the processes it starts (ex: node started by `npx quicktype`).
+pub(crate) fn own_process_group(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// Kill every process in the group led by `pid`, see `own_process_group`.
+pub(crate) fn kill_process_group(pid: u32) {
+    #[cfg(unix)]
+    let _ = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", pid)])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+fn read_in_background<R: Read + Send + 'static>(
+    pipe: Option<R>,
+) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use temp::TempDir;
+
+    /// Whether `pid` is still running, zombies aside.
+    fn running(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| !stat.contains(") Z "))
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn timeout_kills_process_group() {
+        let dir = TempDir::new().unwrap();
+        let pid_file = dir.path().join("pid");
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("sleep 30 & echo $! > {}; wait", pid_file.display()));
+        let cancel = CancelHandle::default();
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(200)),
+            cancel: &cancel,
+        };
+
+        let res = output(&mut cmd, limits);
+        assert!(matches!(res, Err(CodegenError::Timeout(_))), "{:?}", res);
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let pid = pid.trim();
+        let start = Instant::now();
+        while running(pid) && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        assert!(!running(pid), "sleep {} survived", pid);
+    }
+}
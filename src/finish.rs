@@ -0,0 +1,498 @@
+use super::*;
+use std::collections::BTreeMap;
+
+/// Generated files keyed by their path relative to the output directory.
+pub(crate) type Files = BTreeMap<PathBuf, String>;
+
+impl CodegenContext {
+    pub fn finish(&self, lang: Language) -> String {
+        self.try_finish(lang).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_finish(&self, lang: Language) -> Result<String, CodegenError> {
+        self.finish_output(lang).map(|output| output.code)
+    }
+
+    /// Like `try_finish`, but also returns the warnings quicktype printed.
+    pub fn finish_output(&self, lang: Language) -> Result<CodegenOutput, CodegenError> {
+        let (files, warnings) = self.generate(&lang)?;
+        Ok(CodegenOutput {
+            code: single_file(files)?,
+            warnings,
+        })
+    }
+
+    /// Every file quicktype generated, keyed by relative path.
+    /// Required for options such as `GoOptions::multi_file_output`.
+    pub fn finish_files(&self, lang: Language) -> Result<BTreeMap<PathBuf, String>, CodegenError> {
+        self.generate(&lang).map(|(files, _)| files)
+    }
+
+    /// Generate code for every language, running up to `with_parallelism` quicktype processes at once.
+    /// The merged schema is only written once.
+    pub fn finish_many(&self, langs: &[Language]) -> Vec<Result<String, CodegenError>> {
+        let jobs = langs
+            .iter()
+            .map(|lang| self.prepare(lang))
+            .collect::<Vec<_>>();
+        let mut results = jobs
+            .iter()
+            .zip(langs)
+            .map(|(job, lang)| match job {
+                Ok(Job::Done(files, version)) => Some(
+                    self.postprocess(lang, files.clone(), version.as_deref(), &mut vec![])
+                        .and_then(single_file),
+                ),
+                Ok(Job::Run { .. }) => None,
+                Err(e) => Some(Err(e.duplicate())),
+            })
+            .collect::<Vec<_>>();
+        let pending = jobs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, job)| match job {
+                Ok(Job::Run { args, cache_key }) => Some((i, args, cache_key)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if pending.is_empty() {
+            return results.into_iter().flatten().collect();
+        }
+
+        let prepared = self.resolve_runner().and_then(|runner| {
+            let dir = TempDir::new()?;
+            let schema_path = self.write_schema(dir.path())?;
+            Ok((runner, dir, schema_path))
+        });
+        let (runner, dir, schema_path) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                for (i, _, _) in pending {
+                    results[i] = Some(Err(e.duplicate()));
+                }
+                return results.into_iter().flatten().collect();
+            }
+        };
+
+        let parallelism = self
+            .parallelism
+            .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1)
+            .clamp(1, pending.len());
+
+        let next = AtomicUsize::new(0);
+        let shared = Mutex::new(&mut results);
+        std::thread::scope(|scope| {
+            for _ in 0..parallelism {
+                scope.spawn(|| {
+                    while let Some(&(i, args, cache_key)) =
+                        pending.get(next.fetch_add(1, Ordering::Relaxed))
+                    {
+                        let out_dir = dir.path().join(format!("out-{}", i));
+                        let res = self
+                            .run_quicktype(runner, args, &schema_path, &out_dir, &langs[i])
+                            .and_then(|(files, mut warnings)| {
+                                let version = self.generated_version();
+                                self.cache_put(cache_key.as_deref(), &files, version.as_deref());
+                                self.postprocess(
+                                    &langs[i],
+                                    files,
+                                    version.as_deref(),
+                                    &mut warnings,
+                                )
+                            })
+                            .and_then(single_file);
+                        shared.lock().unwrap()[i] = Some(res);
+                    }
+                });
+            }
+        });
+
+        results.into_iter().flatten().collect()
+    }
+
+    fn generate(&self, lang: &Language) -> Result<(Files, Vec<String>), CodegenError> {
+        let (files, version, mut warnings) = match self.prepare(lang)? {
+            Job::Done(files, version) => (files, version, vec![]),
+            Job::Run { args, cache_key } => {
+                let runner = self.resolve_runner()?;
+                let dir = TempDir::new()?;
+                let schema_path = self.write_schema(dir.path())?;
+                let out_dir = dir.path().join("out");
+                let (files, warnings) =
+                    self.run_quicktype(runner, &args, &schema_path, &out_dir, lang)?;
+                let version = self.generated_version();
+                self.cache_put(cache_key.as_deref(), &files, version.as_deref());
+                (files, version, warnings)
+            }
+        };
+        let files = self.postprocess(lang, files, version.as_deref(), &mut warnings)?;
+        Ok((files, warnings))
+    }
+
+    /// Applied to quicktype's output after caching.
+    /// `version` is the quicktype version that generated `files`, if known.
+    pub(crate) fn postprocess(
+        &self,
+        lang: &Language,
+        mut files: Files,
+        version: Option<&str>,
+        warnings: &mut Vec<String>,
+    ) -> Result<Files, CodegenError> {
+        for (path, code) in files.iter_mut() {
+            *code = self.format(lang, path, std::mem::take(code), warnings)?;
+        }
+        if self.header {
+            if let Some(header) = self.header(lang, version)? {
+                for code in files.values_mut() {
+                    code.insert_str(0, &header);
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Everything before running the quicktype cli.
+    /// Code is returned early if it's cached or generated by the `QuicktypeServer`.
+    pub(crate) fn prepare(&self, lang: &Language) -> Result<Job, CodegenError> {
+        let unknown = self.schema.unknown_converters();
+        if !unknown.is_empty() {
+            return Err(CodegenError::UnknownConverters(unknown));
+        }
+        let args = self.quicktype_args(lang);
+        let cache_key = self.cache_key(&args);
+        if let Some((files, version)) = self.cache_get(cache_key.as_deref()) {
+            return Ok(Job::Done(files, version));
+        }
+        self.check_version(lang)?;
+        if let Some(code) = self.try_server(&args) {
+            let files = Files::from([(self.file_name(lang), code)]);
+            let version = self.generated_version();
+            self.cache_put(cache_key.as_deref(), &files, version.as_deref());
+            return Ok(Job::Done(files, version));
+        }
+        Ok(Job::Run { args, cache_key })
+    }
+
+    pub(crate) fn quicktype_args(&self, lang: &Language) -> Vec<String> {
+        let mut args = self
+            .override_quicktype_args
+            .clone()
+            .unwrap_or_else(|| lang.get_args());
+        args.extend(self.extra_quicktype_args.iter().cloned());
+        args
+    }
+
+    /// The name quicktype gives the output file when it only writes one.
+    pub(crate) fn file_name(&self, lang: &Language) -> PathBuf {
+        PathBuf::from(format!("{}.{}", self.base_name, lang.extension()))
+    }
+
+    /// The worker only supports single file output with a wrapper type.
+    fn try_server(&self, args: &[String]) -> Option<String> {
+        if args.iter().any(|arg| arg == MULTI_FILE_OUTPUT)
+            || self.schema.top_level == TopLevel::Omit
+        {
+            return None;
+        }
+        self.server
+            .as_ref()?
+            .generate(&self.schema.to_json(), &self.base_name, args, self.limits())
+            .ok()
+    }
+
+    pub(crate) fn resolve_runner(&self) -> Result<&ResolvedRunner, CodegenError> {
+        if let Some(runner) = self.resolved_runner.get() {
+            return Ok(runner);
+        }
+        let runner = self.runner.resolve(self.limits())?;
+        Ok(self.resolved_runner.get_or_init(|| runner))
+    }
+
+    /// `None` if caching is disabled.
+    /// Asking quicktype for its version spawns node, so the key uses `Runner::fingerprint` instead.
+    fn cache_key(&self, args: &[String]) -> Option<String> {
+        self.cache.as_ref()?;
+        let server = self.server.as_ref().and_then(|server| server.version());
+        let quicktype = format!(
+            "{} {:?} {:?}",
+            self.runner.fingerprint(),
+            server,
+            self.cache_version
+        );
+        let schema = self
+            .schema_files()
+            .0
+            .into_iter()
+            .map(|(path, contents)| format!("{}\n{}", path.display(), contents))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(Cache::key(&schema, &self.base_name, args, &quicktype))
+    }
+
+    /// Cached files and the quicktype version that generated them, if it was known.
+    fn cache_get(&self, key: Option<&str>) -> Option<(Files, Option<String>)> {
+        self.cache.as_ref()?.get(key?)
+    }
+
+    pub(crate) fn cache_put(&self, key: Option<&str>, files: &Files, version: Option<&str>) {
+        if let (Some(cache), Some(key)) = (self.cache.as_ref(), key) {
+            cache.put(key, files, version);
+        }
+    }
+
+    /// The quicktype version to keep with freshly generated code.
+    /// Only looked up when the header needs it, so that cache hits don't have to run quicktype.
+    pub(crate) fn generated_version(&self) -> Option<String> {
+        if self.header {
+            self.version_string().ok()
+        } else {
+            self.known_version()
+        }
+    }
+
+    /// Returns the path quicktype should read the schema from.
+    pub(crate) fn write_schema(&self, dir: &Path) -> Result<PathBuf, CodegenError> {
+        let (files, src) = self.schema_files();
+        for (path, contents) in files {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, contents)?;
+        }
+        Ok(dir.join(src))
+    }
+
+    /// The files quicktype reads, relative to a temporary directory, and which one to pass it.
+    /// Without a wrapper type, quicktype is given a directory with a `.schema` file per top level type,
+    /// each of which is named after its file.
+    pub(crate) fn schema_files(&self) -> (Vec<(PathBuf, String)>, PathBuf) {
+        let schema_path = PathBuf::from("schema.json");
+        let mut files = vec![(schema_path.clone(), self.schema.to_json())];
+        let top_levels = self.schema.omitted_top_levels();
+        if top_levels.is_empty() {
+            return (files, schema_path);
+        }
+        let dir = PathBuf::from("top-level");
+        for name in top_levels {
+            let source = object! {
+                "$ref": format!(
+                    "../schema.json#/{}/{}",
+                    self.schema.draft.definitions_key(),
+                    name
+                )
+            };
+            files.push((dir.join(format!("{}.schema", name)), source.to_string()));
+        }
+        (files, dir)
+    }
+
+    fn run_quicktype(
+        &self,
+        runner: &ResolvedRunner,
+        args: &[String],
+        schema_path: &Path,
+        out_dir: &Path,
+        lang: &Language,
+    ) -> Result<(Files, Vec<String>), CodegenError> {
+        std::fs::create_dir_all(out_dir)?;
+        let out_path = out_dir.join(self.file_name(lang));
+        let mut cmd = self.quicktype_command(runner, args, schema_path, &out_path);
+        let output = exec::output(&mut cmd, self.limits())?;
+        let warnings = check_output(output, out_dir)?;
+        Ok((read_files(out_dir)?, warnings))
+    }
+
+    /// In multi file mode, quicktype writes every file next to `out_path`.
+    pub(crate) fn quicktype_command(
+        &self,
+        runner: &ResolvedRunner,
+        args: &[String],
+        schema_path: &Path,
+        out_path: &Path,
+    ) -> std::process::Command {
+        let mut cmd = runner.command();
+        cmd.args(["--quiet", "-t", &self.base_name, "-o"])
+            .arg(out_path)
+            .args(["--src-lang", "schema"])
+            .arg(schema_path)
+            .args(args);
+        cmd
+    }
+}
+
+pub(crate) enum Job {
+    /// Generated code and the quicktype version that generated it, if known
+    Done(Files, Option<String>),
+    Run {
+        args: Vec<String>,
+        cache_key: Option<String>,
+    },
+}
+
+pub(crate) fn single_file(files: Files) -> Result<String, CodegenError> {
+    if files.len() > 1 {
+        return Err(CodegenError::MultipleFiles(files.into_keys().collect()));
+    }
+    files
+        .into_values()
+        .next()
+        .ok_or(CodegenError::OutputMissing)
+}
+
+/// Every file under `dir`, keyed by relative path.
+pub(crate) fn read_files(dir: &Path) -> Result<Files, CodegenError> {
+    fn visit(root: &Path, dir: &Path, files: &mut Files) -> Result<(), CodegenError> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                visit(root, &path, files)?;
+            } else {
+                let code = String::from_utf8(std::fs::read(&path)?)?;
+                files.insert(path.strip_prefix(root).unwrap().to_owned(), code);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Files::new();
+    visit(dir, dir, &mut files)?;
+    Ok(files)
+}
+
+/// Fail if quicktype exited unsuccessfully or did not write anything to `out_dir`.
+/// Otherwise, everything quicktype printed to stderr is returned as warnings.
+pub(crate) fn check_output(
+    output: std::process::Output,
+    out_dir: &Path,
+) -> Result<Vec<String>, CodegenError> {
+    let stderr = String::from_utf8(output.stderr)?;
+    let (warnings, errors): (Vec<_>, Vec<_>) = stderr
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .partition(|line| is_warning(line));
+
+    let has_output = std::fs::read_dir(out_dir)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+
+    // Older quicktype releases print errors but exit successfully.
+    if !output.status.success() || (!has_output && !errors.is_empty()) {
+        return Err(CodegenError::QuicktypeFailed {
+            stderr: if errors.is_empty() {
+                stderr.trim().to_owned()
+            } else {
+                errors.join("\n")
+            },
+            exit_status: output.status,
+        });
+    }
+
+    if !has_output {
+        return Err(CodegenError::OutputMissing);
+    }
+    Ok(warnings
+        .into_iter()
+        .chain(errors)
+        .map(|s| s.to_owned())
+        .collect())
+}
+
+/// npm notices, deprecations and the like.
+fn is_warning(line: &str) -> bool {
+    let line = line.trim_start().to_lowercase();
+    ["npm warn", "npm notice", "warn", "(node:", "deprecat"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+        || line.contains("deprecated")
+        || line.contains("experimentalwarning")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+
+    fn output(code: i32, stderr: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(code << 8),
+            stdout: vec![],
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    /// An output directory, with a generated file if `generated`.
+    fn out_dir(generated: bool) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        if generated {
+            std::fs::write(dir.path().join("T.ts"), "code").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn warnings() {
+        for line in [
+            "npm WARN exec The following package was not found",
+            "npm notice New major version of npm available!",
+            "(node:123) ExperimentalWarning: The Fetch API is experimental",
+            "  warning: something",
+            "The `punycode` module is deprecated.",
+        ] {
+            assert!(is_warning(line), "{}", line);
+        }
+        assert!(!is_warning("Error: Cannot read schema"));
+    }
+
+    #[test]
+    fn npm_warnings_succeed() {
+        let dir = out_dir(true);
+        let stderr = "npm WARN exec The following package was not found\n\nnpm notice update\n";
+        assert_eq!(
+            check_output(output(0, stderr), dir.path()).unwrap(),
+            [
+                "npm WARN exec The following package was not found",
+                "npm notice update"
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_without_output_fail() {
+        let dir = out_dir(false);
+        let stderr = "npm WARN deprecated\nError: Cannot read schema\n";
+        match check_output(output(0, stderr), dir.path()) {
+            Err(CodegenError::QuicktypeFailed {
+                stderr,
+                exit_status,
+            }) => {
+                assert_eq!(stderr, "Error: Cannot read schema");
+                assert!(exit_status.success());
+            }
+            res => panic!("{:?}", res),
+        }
+
+        assert!(matches!(
+            check_output(output(0, ""), dir.path()),
+            Err(CodegenError::OutputMissing)
+        ));
+    }
+
+    #[test]
+    fn failure_with_only_warnings() {
+        let dir = out_dir(true);
+        let stderr = "npm WARN exec something\nnpm notice other\n";
+        match check_output(output(1, stderr), dir.path()) {
+            Err(CodegenError::QuicktypeFailed {
+                stderr,
+                exit_status,
+            }) => {
+                assert_eq!(stderr, "npm WARN exec something\nnpm notice other");
+                assert_eq!(exit_status.code(), Some(1));
+            }
+            res => panic!("{:?}", res),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::*;
+use std::fmt;
+use std::process::Command;
+
+/// Returns the formatted code or an error message.
+pub type FormatFn = dyn Fn(&Language, &str) -> Result<String, String> + Send + Sync;
+
+/// Formats generated code, see `CodegenContext::with_formatter`.
+#[derive(Clone)]
+pub enum Formatter {
+    /// The usual formatter of each language:
+    /// `prettier` for Typescript, `rustfmt` for Rust, `gofmt` for Go, `dart format` for Dart and `black` for Python.
+    /// Other languages are left as is.
+    Preset,
+    /// A program and its arguments that reads code from stdin and writes the formatted code to stdout
+    Command(Vec<String>),
+    Custom(Arc<FormatFn>),
+}
+
+impl Formatter {
+    pub fn command(program: &str, args: &[&str]) -> Self {
+        Formatter::Command(
+            std::iter::once(program)
+                .chain(args.iter().copied())
+                .map(|s| s.to_owned())
+                .collect(),
+        )
+    }
+
+    pub fn custom(
+        f: impl Fn(&Language, &str) -> Result<String, String> + Send + Sync + 'static,
+    ) -> Self {
+        Formatter::Custom(Arc::new(f))
+    }
+
+    /// The command used by `Formatter::Preset`, `None` if there is none for `lang`.
+    pub fn preset_command(lang: &Language, file_name: &Path) -> Option<Vec<String>> {
+        let command: &[&str] = match lang {
+            Language::Typescript(_) => &["prettier", "--stdin-filepath"],
+            Language::Rust(_) => &["rustfmt", "--edition", "2021"],
+            Language::Go(_) => &["gofmt"],
+            Language::Dart(_) => &["dart", "format"],
+            Language::Python(_) => &["black", "-q", "-"],
+            _ => return None,
+        };
+        let mut command = command.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        if let Language::Typescript(_) = lang {
+            command.push(file_name.to_string_lossy().into_owned());
+        }
+        Some(command)
+    }
+}
+
+impl fmt::Debug for Formatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Formatter::Preset => write!(f, "Preset"),
+            Formatter::Command(command) => f.debug_tuple("Command").field(command).finish(),
+            Formatter::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+impl CodegenContext {
+    /// Run generated code through `formatter`.
+    /// If the formatter isn't installed, the code is left unformatted and a warning is returned.
+    pub fn with_formatter(mut self, formatter: Formatter) -> Self {
+        self.formatter = Some(formatter);
+        self
+    }
+
+    pub(crate) fn format(
+        &self,
+        lang: &Language,
+        path: &Path,
+        code: String,
+        warnings: &mut Vec<String>,
+    ) -> Result<String, CodegenError> {
+        let command = match &self.formatter {
+            None => return Ok(code),
+            Some(Formatter::Custom(f)) => {
+                return f(lang, &code).map_err(|stderr| CodegenError::FormatterFailed {
+                    formatter: "custom formatter".to_owned(),
+                    stderr,
+                })
+            }
+            Some(Formatter::Command(command)) => command.clone(),
+            Some(Formatter::Preset) => match Formatter::preset_command(lang, path) {
+                Some(command) => command,
+                None => return Ok(code),
+            },
+        };
+        let Some((program, args)) = command.split_first() else {
+            return Ok(code);
+        };
+
+        let output = match exec::output_with_input(
+            Command::new(program).args(args),
+            Some(&code),
+            self.limits(),
+        ) {
+            Err(CodegenError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                warnings.push(format!("{} is not installed, skipped formatting", program));
+                return Ok(code);
+            }
+            res => res?,
+        };
+        if !output.status.success() {
+            return Err(CodegenError::FormatterFailed {
+                formatter: command.join(" "),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            });
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
@@ -0,0 +1,15 @@
+/// 128 bit FNV-1a over length-prefixed parts, as lowercase hex.
+/// Unlike `std::hash`, this is stable across rust versions and platforms.
+pub(crate) fn hash_hex(parts: &[&[u8]]) -> String {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    let mut hash = OFFSET;
+    for part in parts {
+        for byte in (part.len() as u64).to_le_bytes().iter().chain(part.iter()) {
+            hash ^= *byte as u128;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    format!("{:032x}", hash)
+}
@@ -0,0 +1,43 @@
+use super::*;
+
+impl CodegenContext {
+    /// Prepend a comment to generated code stating that it must not be edited by hand,
+    /// the versions of this crate and quicktype, and `schema_hash`.
+    /// Json schema output has no comment syntax and is left untouched.
+    pub fn with_header(mut self) -> Self {
+        self.header = true;
+        self
+    }
+
+    /// A stable hash of the merged schema, as included in the generated file header.
+    pub fn schema_hash(&self) -> String {
+        hash::hash_hex(&[self.schema.to_json().as_bytes()])
+    }
+
+    /// `version` is the quicktype version that generated the code, looked up if `None`.
+    pub(crate) fn header(
+        &self,
+        lang: &Language,
+        version: Option<&str>,
+    ) -> Result<Option<String>, CodegenError> {
+        let Some(comment) = lang.comment_prefix() else {
+            return Ok(None);
+        };
+        let version = match version {
+            Some(version) => version.to_owned(),
+            None => self.version_string()?,
+        };
+        let version = match QuicktypeVersion::parse(&version) {
+            Some(version) => version.to_string(),
+            None => "unknown".to_owned(),
+        };
+        Ok(Some(format!(
+            "{comment} Code generated by quick-type-schema {} with quicktype {}. DO NOT EDIT.\n\
+             {comment} Schema hash: {}\n\n",
+            env!("CARGO_PKG_VERSION"),
+            version,
+            self.schema_hash(),
+            comment = comment,
+        )))
+    }
+}
@@ -1,23 +1,42 @@
 use super::*;
 
+pub(crate) const MULTI_FILE_OUTPUT: &str = "--multi-file-output";
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "language"))]
 pub enum Language {
+    #[cfg_attr(feature = "serde", serde(rename = "typescript"))]
     Typescript(TypescriptOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "schema"))]
     JsonSchema(JsonSchemaOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "csharp"))]
     CSharp(CSharpOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "crystal"))]
     Crystal(CrystalOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "dart"))]
     Dart(DartOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "elm"))]
     Elm(ElmOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "go"))]
     Go(GoOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "haskell"))]
     Haskell(HaskellOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "python"))]
     Python(PythonOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "ruby"))]
     Ruby(RubyOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "rust"))]
     Rust(RustOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "smithy"))]
     Smithy(SmithyOptions),
+    #[cfg_attr(feature = "serde", serde(rename = "swift"))]
     Swift(SwiftOptions),
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct TypescriptOptions {
     /// Interfaces only
     pub just_types: bool,
@@ -36,9 +55,13 @@ pub struct TypescriptOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct JsonSchemaOptions {}
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct CSharpOptions {
     /// Serialization framework
     pub framework: CSharpFramework,
@@ -67,9 +90,13 @@ pub struct CSharpOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct CrystalOptions {}
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct DartOptions {
     /// Null Safety
     pub null_safety: bool,
@@ -94,6 +121,8 @@ pub struct DartOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct ElmOptions {
     /// Plain types only
     pub just_types: bool,
@@ -104,6 +133,8 @@ pub struct ElmOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct GoOptions {
     /// Plain types only
     pub just_types: bool,
@@ -111,8 +142,8 @@ pub struct GoOptions {
     pub just_types_and_package: bool,
     /// Generated package name
     pub package: String,
-    // Exposing this attribute breaks codegen.
-    // multi_file_output: bool,
+    /// Renders each top-level object in its own Go file (use `CodegenContext::finish_files`)
+    pub multi_file_output: bool,
     /// list of tags which should be generated for fields
     pub field_tags: String,
     /// If set, all non-required objects will be tagged with ",omitempty"
@@ -120,6 +151,8 @@ pub struct GoOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct HaskellOptions {
     /// Plain types only
     pub just_types: bool,
@@ -130,6 +163,8 @@ pub struct HaskellOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct PythonOptions {
     /// Python Version
     pub python_version: PythonVersion,
@@ -140,6 +175,8 @@ pub struct PythonOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct RubyOptions {
     /// Plain types only
     pub just_types: bool,
@@ -150,6 +187,8 @@ pub struct RubyOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct RustOptions {
     /// Density
     pub density: Density,
@@ -170,6 +209,8 @@ pub struct RustOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct SmithyOptions {
     /// Serialization framework
     pub framework: SmithyFramework,
@@ -178,6 +219,8 @@ pub struct SmithyOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct SwiftOptions {
     /// Plain types only
     pub just_types: bool,
@@ -209,8 +252,8 @@ pub struct SwiftOptions {
     pub swift_5_support: bool,
     /// Mark generated models as Sendable
     pub sendable: bool,
-    // Exposing this attribute breaks codegen.
-    // multi_file_output: bool,
+    /// Renders each top-level object in its own Swift file (use `CodegenContext::finish_files`)
+    pub multi_file_output: bool,
     /// Access level
     pub access_level: SwiftAccessLevel,
     /// Make types implement protocol   
@@ -218,6 +261,8 @@ pub struct SwiftOptions {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum ArrayOrList {
     #[default]
     Array,
@@ -234,6 +279,8 @@ impl ArrayOrList {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum StructOrClass {
     #[default]
     Struct,
@@ -250,6 +297,8 @@ impl StructOrClass {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Density {
     #[default]
     Normal,
@@ -266,6 +315,8 @@ impl Density {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum AcronymStyle {
     #[default]
     Original,
@@ -286,13 +337,18 @@ impl AcronymStyle {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum CSharpFramework {
     #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "newtonsoft"))]
     NewtonSoft,
     SystemTextJson,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum CSharpNumberType {
     #[default]
     Double,
@@ -300,6 +356,8 @@ pub enum CSharpNumberType {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum CSharpAnyType {
     #[default]
     Object,
@@ -307,13 +365,18 @@ pub enum CSharpAnyType {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CSharpVersion {
+    #[cfg_attr(feature = "serde", serde(rename = "5"))]
     V5,
+    #[cfg_attr(feature = "serde", serde(rename = "6"))]
     #[default]
     V6,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum CSharpFeatures {
     #[default]
     Complete,
@@ -323,6 +386,8 @@ pub enum CSharpFeatures {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum CSharpBaseClass {
     EntityData,
     #[default]
@@ -330,14 +395,20 @@ pub enum CSharpBaseClass {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PythonVersion {
+    #[cfg_attr(feature = "serde", serde(rename = "3.5"))]
     V3_5,
+    #[cfg_attr(feature = "serde", serde(rename = "3.6"))]
     #[default]
     V3_6,
+    #[cfg_attr(feature = "serde", serde(rename = "3.7"))]
     V3_7,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum RubyStrictness {
     #[default]
     Strict,
@@ -346,6 +417,8 @@ pub enum RubyStrictness {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum RustVisibility {
     #[default]
     Private,
@@ -354,12 +427,16 @@ pub enum RustVisibility {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum SmithyFramework {
     #[default]
     JustTypes,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum SwiftAccessLevel {
     #[default]
     Internal,
@@ -367,6 +444,8 @@ pub enum SwiftAccessLevel {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum SwiftProtocol {
     #[default]
     None,
@@ -447,6 +526,7 @@ impl Language {
                 .opt_bool("--just-types", opt.just_types)
                 .opt_bool("--just-types-and-package", opt.just_types_and_package)
                 .opt_string("--package", &opt.package)
+                .opt_bool(MULTI_FILE_OUTPUT, opt.multi_file_output)
                 .opt_string("--field-tags", &opt.field_tags)
                 .opt_bool("--omit-empty", opt.omit_empty)
                 .build(),
@@ -515,6 +595,7 @@ impl Language {
                 .opt_bool("--optional-enums", opt.optional_enums)
                 .opt_bool("--swift-5-support", opt.swift_5_support)
                 .opt_bool("--sendable", opt.sendable)
+                .opt_bool(MULTI_FILE_OUTPUT, opt.multi_file_output)
                 .opt_enum("--access-level", &opt.access_level, || {
                     match opt.access_level {
                         SwiftAccessLevel::Internal => "internal",
@@ -547,6 +628,71 @@ impl Language {
             Language::Swift(_) => "swift",
         }
     }
+
+    /// The file extension of generated code.
+    pub fn extension(&self) -> &str {
+        match self {
+            Language::Typescript(_) => "ts",
+            Language::JsonSchema(_) => "json",
+            Language::CSharp(_) => "cs",
+            Language::Crystal(_) => "cr",
+            Language::Dart(_) => "dart",
+            Language::Elm(_) => "elm",
+            Language::Go(_) => "go",
+            Language::Haskell(_) => "hs",
+            Language::Python(_) => "py",
+            Language::Ruby(_) => "rb",
+            Language::Rust(_) => "rs",
+            Language::Smithy(_) => "smithy",
+            Language::Swift(_) => "swift",
+        }
+    }
+
+    /// The line comment syntax of the language, `None` if it has none.
+    pub fn comment_prefix(&self) -> Option<&str> {
+        match self {
+            Language::JsonSchema(_) => None,
+            Language::Crystal(_) | Language::Python(_) | Language::Ruby(_) => Some("#"),
+            Language::Elm(_) | Language::Haskell(_) => Some("--"),
+            Language::Typescript(_)
+            | Language::CSharp(_)
+            | Language::Dart(_)
+            | Language::Go(_)
+            | Language::Rust(_)
+            | Language::Smithy(_)
+            | Language::Swift(_) => Some("//"),
+        }
+    }
+
+    /// Enabled options that only exist in newer quicktype releases,
+    /// along with the lowest release known to support them.
+    pub fn version_requirements(&self) -> Vec<(&'static str, QuicktypeVersion)> {
+        const V15: QuicktypeVersion = QuicktypeVersion::new(15, 0, 0);
+        const V20: QuicktypeVersion = QuicktypeVersion::new(20, 0, 0);
+        const V23: QuicktypeVersion = QuicktypeVersion::new(23, 0, 0);
+
+        let opts: &[(&'static str, bool, QuicktypeVersion)] = match self {
+            Language::Typescript(opt) => &[("--prefer-const-values", opt.prefer_const_values, V20)],
+            Language::CSharp(opt) => &[("--keep-property-name", opt.keep_property_name, V23)],
+            Language::Dart(opt) => &[
+                ("--use-freezed", opt.use_freezed, V15),
+                ("--use-hive", opt.use_hive, V15),
+                ("--use-json-annotation", opt.use_json_annotation, V23),
+            ],
+            Language::Rust(opt) => &[
+                ("--derive-debug", opt.derive_debug, V23),
+                ("--derive-clone", opt.derive_clone, V23),
+                ("--derive-partial-eq", opt.derive_partial_eq, V23),
+                ("--skip-serializing-none", opt.skip_serializing_none, V23),
+            ],
+            Language::Swift(opt) => &[("--sendable", opt.sendable, V23)],
+            _ => &[],
+        };
+        opts.iter()
+            .filter(|(_, enabled, _)| *enabled)
+            .map(|(flag, _, version)| (*flag, *version))
+            .collect()
+    }
 }
 
 impl Default for CSharpOptions {
@@ -601,6 +747,7 @@ impl Default for GoOptions {
             just_types: false,
             just_types_and_package: false,
             package: "main".to_owned(),
+            multi_file_output: false,
             field_tags: "json".to_owned(),
             omit_empty: false,
         }
@@ -659,8 +806,70 @@ impl Default for SwiftOptions {
             optional_enums: false,
             swift_5_support: false,
             sendable: false,
+            multi_file_output: false,
             access_level: SwiftAccessLevel::default(),
             protocol: SwiftProtocol::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize() {
+        let lang: Language =
+            serde_json::from_str(r#"{ "language": "go", "package": "api" }"#).unwrap();
+        assert_eq!(
+            lang,
+            Language::Go(GoOptions {
+                package: "api".to_owned(),
+                ..Default::default()
+            })
+        );
+
+        let err = serde_json::from_str::<Language>(r#"{ "language": "go", "pakage": "api" }"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("pakage"), "{}", err);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn csharp_enums() {
+        let lang = Language::CSharp(CSharpOptions {
+            framework: CSharpFramework::SystemTextJson,
+            base_class: CSharpBaseClass::EntityData,
+            ..Default::default()
+        });
+        let val = serde_json::to_value(&lang).unwrap();
+        assert_eq!(val["framework"], "system-text-json");
+        assert_eq!(val["base_class"], "entity-data");
+        assert_eq!(serde_json::from_value::<Language>(val).unwrap(), lang);
+        assert_eq!(
+            serde_json::to_value(CSharpFramework::NewtonSoft).unwrap(),
+            "newtonsoft"
+        );
+    }
+
+    #[test]
+    fn version_requirements() {
+        assert_eq!(
+            Language::Rust(Default::default()).version_requirements(),
+            []
+        );
+        let lang = Language::Dart(DartOptions {
+            use_freezed: true,
+            use_json_annotation: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            lang.version_requirements(),
+            [
+                ("--use-freezed", QuicktypeVersion::new(15, 0, 0)),
+                ("--use-json-annotation", QuicktypeVersion::new(23, 0, 0)),
+            ]
+        );
+    }
+}
@@ -1,33 +1,88 @@
-use json::{object, JsonValue};
-
-#[cfg(feature = "add_type")]
-use schemars::{schema_for, JsonSchema};
+use json::object;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 
+#[cfg(any(feature = "schemars08", feature = "schemars1"))]
+mod add_type;
+#[cfg(feature = "tokio")]
+mod async_finish;
+pub mod build_support;
+mod cache;
+mod check;
 mod cli_builder;
+mod error;
+mod exec;
+mod finish;
+mod format;
+mod hash;
+mod header;
 mod langs;
+#[cfg(feature = "registry")]
+mod registry;
+mod runner;
+mod schema;
+mod server;
+mod temp;
+mod version;
+mod write;
 
+use cache::Cache;
 use cli_builder::CliBuilder;
+pub use error::*;
+pub use exec::CancelHandle;
+use exec::Limits;
+pub use format::{FormatFn, Formatter};
 pub use langs::*;
+#[cfg(feature = "registry")]
+pub use registry::*;
+use runner::ResolvedRunner;
+pub use runner::*;
+pub use schema::*;
+pub use server::*;
+use temp::TempDir;
+pub use version::*;
+
+/// Generated code along with anything quicktype printed that wasn't fatal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodegenOutput {
+    pub code: String,
+    pub warnings: Vec<String>,
+}
 
 #[derive(Debug, Clone)]
 pub struct CodegenContext {
     base_name: String,
     schema: Schema,
     override_quicktype_args: Option<Vec<String>>,
+    extra_quicktype_args: Vec<String>,
+    parallelism: Option<usize>,
+    server: Option<Arc<QuicktypeServer>>,
+    cache: Option<Cache>,
+    cache_version: Option<String>,
+    runner: Runner,
+    resolved_runner: Arc<OnceLock<ResolvedRunner>>,
+    timeout: Option<Duration>,
+    cancel: CancelHandle,
+    header: bool,
+    formatter: Option<Formatter>,
+    #[cfg(feature = "schemars08")]
+    schema_settings: Option<add_type::Settings>,
+    #[cfg(feature = "schemars1")]
+    generator_v1: Option<Arc<Mutex<schemars1::generate::SchemaGenerator>>>,
 }
 
 impl CodegenContext {
     pub fn new(base_name: &str, override_quicktype_args: Option<&[&str]>) -> Self {
         let schema = Schema {
-            current_num: 0,
-            final_val: object! {
-                "$schema": "http://json-schema.org/draft-07/schema#",
-                "type": "object",
-                "definitions": {
-
-                },
-            },
+            definitions: object! {},
+            top_levels: vec![],
             raw_schemas: vec![],
+            conflict_strategy: ConflictStrategy::default(),
+            top_level: TopLevel::default(),
+            converters: None,
+            draft: Draft::default(),
         };
         CodegenContext {
             base_name: base_name.to_owned(),
@@ -38,117 +93,193 @@ impl CodegenContext {
                     .map(|s| s.to_string())
                     .collect()
             }),
+            extra_quicktype_args: vec![],
+            parallelism: None,
+            server: None,
+            cache: None,
+            cache_version: None,
+            runner: Runner::default(),
+            resolved_runner: Arc::new(OnceLock::new()),
+            timeout: None,
+            cancel: CancelHandle::default(),
+            header: false,
+            formatter: None,
+            #[cfg(feature = "schemars08")]
+            schema_settings: None,
+            #[cfg(feature = "schemars1")]
+            generator_v1: None,
         }
     }
 
-    #[cfg(feature = "add_type")]
-    pub fn add_type<T: JsonSchema>(&mut self) {
-        self.schema
-            .push_schema_str(&serde_json::to_string(&schema_for!(T)).unwrap());
+    /// Kill quicktype and fail with `CodegenError::Timeout` if a single invocation takes longer than `timeout`.
+    /// This also applies to probing runners and to requests to a `QuicktypeServer`,
+    /// whose worker is killed before falling back to the quicktype cli.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
 
-    pub fn add_schema(&mut self, schema: &str) {
-        self.schema.push_schema_str(schema);
+    /// A handle that kills running quicktype processes from another thread.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
     }
 
-    pub fn finish(&self, lang: Language) -> String {
-        let args = lang.get_args();
-        let proc_id = std::process::id();
-
-        let mut out_path = std::env::temp_dir();
-        out_path.push(format!("quick-type-code-{}-{}", lang.name(), proc_id));
-
-        let mut schema_path = std::env::temp_dir();
-        schema_path.push(format!("quick-type-schema-{}.json", proc_id));
-
-        std::fs::write(&schema_path, self.schema.final_val.to_string()).unwrap();
-
-        let mut quicktype_args = [
-            "--quiet",
-            "-t",
-            &self.base_name,
-            "-o",
-            out_path.to_str().unwrap(),
-            "--src-lang",
-            "schema",
-            schema_path.to_str().unwrap(),
-        ]
-        .into_iter()
-        .map(|s| s.to_owned())
-        .collect::<Vec<_>>();
-        if let Some(overrides) = self.override_quicktype_args.as_ref() {
-            quicktype_args.append(&mut overrides.clone());
-        } else {
-            quicktype_args.append(&mut args.to_vec());
-        }
+    /// Pass `args` to quicktype after the arguments of the `Language`, ex: `&["--acronym-style", "camel"]`.
+    /// Unlike override arguments, the quicktype version is still checked against the language's options.
+    pub fn with_extra_args(mut self, args: &[&str]) -> Self {
+        self.extra_quicktype_args
+            .extend(args.iter().map(|s| s.to_string()));
+        self
+    }
 
-        let cmd = if std::process::Command::new("quicktype")
-            .arg("--version")
-            .output()
-            .is_ok()
-        {
-            "quicktype"
-        } else if std::process::Command::new("npx")
-            .arg("--version")
-            .output()
-            .is_ok()
-        {
-            quicktype_args.insert(0, "quicktype".to_owned());
-            "npx"
-        } else {
-            panic!("Neither `quicktype` and `npx` are in $PATH")
-        };
+    /// Choose how quicktype is invoked, see `Runner`.
+    pub fn with_runner(mut self, runner: Runner) -> Self {
+        self.runner = runner;
+        self.resolved_runner = Arc::new(OnceLock::new());
+        self
+    }
+
+    /// The version of quicktype that `finish` will run.
+    /// When a `QuicktypeServer` is used, this is the version of `quicktype-core`.
+    pub fn quicktype_version(&self) -> Result<QuicktypeVersion, CodegenError> {
+        let version = self.version_string()?;
+        QuicktypeVersion::parse(&version).ok_or(CodegenError::UnknownVersion(version))
+    }
 
-        let out = String::from_utf8(
-            std::process::Command::new(cmd)
-                .args(quicktype_args.iter())
-                .output()
-                .unwrap()
-                .stderr,
+    fn version_string(&self) -> Result<String, CodegenError> {
+        Ok(
+            match self.server.as_ref().and_then(|server| server.version()) {
+                Some(version) => format!("quicktype-core {}", version),
+                None => self.resolve_runner()?.version(self.limits())?.to_owned(),
+            },
         )
-        .unwrap();
+    }
 
-        if !out.is_empty() {
-            panic!("quicktype {}", out);
+    /// The quicktype version, if it's known without running quicktype.
+    fn known_version(&self) -> Option<String> {
+        match self.server.as_ref().and_then(|server| server.version()) {
+            Some(version) => Some(format!("quicktype-core {}", version)),
+            None => self
+                .resolved_runner
+                .get()?
+                .known_version()
+                .map(|v| v.to_owned()),
         }
+    }
 
-        if !out_path.exists() {
-            panic!("Error: quicktype generated an unexpected noutput");
+    fn limits(&self) -> Limits<'_> {
+        Limits {
+            timeout: self.timeout,
+            cancel: &self.cancel,
         }
+    }
 
-        let output = std::fs::read_to_string(&out_path).unwrap();
-        let _ = std::fs::remove_file(out_path);
-        let _ = std::fs::remove_file(schema_path);
-        output
+    /// Fail early if `lang` enables options that the installed quicktype doesn't have.
+    fn check_version(&self, lang: &Language) -> Result<(), CodegenError> {
+        if self.override_quicktype_args.is_some() {
+            return Ok(());
+        }
+        let requirements = lang.version_requirements();
+        let Some((option, required)) = requirements.into_iter().max_by_key(|(_, v)| *v) else {
+            return Ok(());
+        };
+        let found = self.quicktype_version()?;
+        if found < required {
+            return Err(CodegenError::UnsupportedOption {
+                option,
+                required,
+                found,
+            });
+        }
+        Ok(())
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct Schema {
-    current_num: usize,
-    final_val: JsonValue,
-    raw_schemas: Vec<(String, String)>,
-}
+    /// Find quicktype according to the configured `Runner` and report what was tried.
+    pub fn discover_runner(&self) -> Result<&DiscoveryReport, CodegenError> {
+        Ok(&self.resolve_runner()?.report)
+    }
 
-impl Schema {
-    pub fn push_schema_str(&mut self, s: &str) {
-        let val = json::parse(s).unwrap();
-        let title = val["title"].as_str().unwrap();
+    /// Cache generated code in `dir`, keyed by a hash of the schema, base name, quicktype arguments and installed quicktype.
+    /// Something like `target/quick-type-schema` or `$OUT_DIR` works well.
+    /// quicktype is identified without running it, which package runners such as `npx` don't allow, see `with_cache_version`.
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache = Some(Cache::new(dir.into()));
+        self
+    }
 
-        self.raw_schemas.push((title.to_owned(), s.to_owned()));
+    /// Add `version` to the cache key, ex: the quicktype version pinned by your project,
+    /// so that upgrading quicktype invalidates cached code.
+    pub fn with_cache_version(mut self, version: &str) -> Self {
+        self.cache_version = Some(version.to_owned());
+        self
+    }
 
-        self.final_val["definitions"][title] = val.clone();
-        self.final_val["properties"][format!("t{}", self.current_num)] = object! {
-            "$ref": format!("#/definitions/{}", title)
-        };
-        let JsonValue::Object(val) = val else {
-            unreachable!()
-        };
-        if let Some(definitions) = val.get("definitions") {
-            for (k, v) in definitions.entries() {
-                self.final_val["definitions"][k] = v.clone();
-            }
-        }
-        self.current_num += 1;
+    /// Generate code through a persistent `QuicktypeServer` instead of spawning quicktype every time.
+    /// If the server fails, the quicktype cli is used instead.
+    pub fn with_server(mut self, server: Arc<QuicktypeServer>) -> Self {
+        self.server = Some(server);
+        self
+    }
+
+    /// Start a `QuicktypeServer` for this context.
+    /// If it can't be started, the quicktype cli is used as usual.
+    /// Starting is subject to `with_timeout` and the `cancel_handle`, so call `with_timeout` first.
+    pub fn with_worker(mut self) -> Self {
+        self.server = QuicktypeServer::start_with_limits(self.limits())
+            .ok()
+            .map(Arc::new);
+        self
+    }
+
+    /// Limit how many quicktype processes `finish_many` runs at once.
+    /// Defaults to the available parallelism.
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = Some(parallelism);
+        self
+    }
+
+    /// Choose how definitions that share a name but differ in shape are handled.
+    /// Must be set before adding types to take effect.
+    pub fn with_conflict_strategy(mut self, strategy: ConflictStrategy) -> Self {
+        self.schema.conflict_strategy = strategy;
+        self
+    }
+
+    /// Choose the json schema draft of the merged schema, see `Draft`.
+    pub fn with_draft(mut self, draft: Draft) -> Self {
+        self.schema.draft = draft;
+        self
+    }
+
+    /// Choose what quicktype generates as the top level type, see `TopLevel`.
+    pub fn with_top_level(mut self, top_level: TopLevel) -> Self {
+        self.schema.top_level = top_level;
+        self
+    }
+
+    /// Only include the types named `converters` (their titles) as top level types.
+    /// Other types are only generated if a top level type refers to them.
+    /// Names that don't match an added type fail with `CodegenError::UnknownConverters`.
+    pub fn with_converters(mut self, converters: &[&str]) -> Self {
+        self.schema.converters = Some(converters.iter().map(|s| s.to_string()).collect());
+        self
+    }
+
+    pub fn add_schema(&mut self, schema: &str) {
+        self.schema.push_schema_str(schema);
+    }
+
+    pub fn try_add_schema(&mut self, schema: &str) -> Result<(), SchemaError> {
+        self.schema.try_push_schema_str(schema, None)
+    }
+
+    /// Like `try_add_schema`, but uses `fallback_title` when the schema has no `title`.
+    pub fn try_add_schema_with_title(
+        &mut self,
+        schema: &str,
+        fallback_title: &str,
+    ) -> Result<(), SchemaError> {
+        self.schema
+            .try_push_schema_str(schema, Some(fallback_title))
     }
 }
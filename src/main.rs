@@ -0,0 +1,204 @@
+//! Generate bindings from a config file, for projects that aren't written in rust.
+//!
+//! ```toml
+//! top_level = "Api"
+//! inputs = ["schemas/", "extra.json"]
+//!
+//! [[targets]]
+//! language = "typescript"
+//! output = "web/src/api.ts"
+//! just_types = true
+//! options = { acronym-style = "camel" }
+//! ```
+//!
+//! Targets take the fields of the language's options (ex: `TypescriptOptions`).
+//! `options` are passed to quicktype as flags: `true` as `--name`, strings and numbers as `--name value`.
+//! Paths are relative to the config file.
+
+use quick_type_schema::{CodegenContext, Drift, Language};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: quick-type-schema [--check] [config]
+
+Generates bindings as configured in `config` (default: quick-type-schema.toml).
+Configs ending in `.json` are read as json, otherwise toml.
+
+    --check    Fail if an output is missing or out of date instead of writing it";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    /// The name of the top level type
+    top_level: String,
+    /// Schema files, or directories of `.json` schema files
+    inputs: Vec<PathBuf>,
+    #[serde(default)]
+    header: bool,
+    targets: Vec<Target>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    #[serde(flatten)]
+    language: Language,
+    /// Extra quicktype flags
+    #[serde(default)]
+    options: BTreeMap<String, serde_json::Value>,
+    output: PathBuf,
+}
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut config_path = PathBuf::from("quick-type-schema.toml");
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--check" => check = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("unknown option `{}`\n\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
+            }
+            _ => config_path = arg.into(),
+        }
+    }
+
+    match run(&config_path, check) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Returns whether every target succeeded.
+fn run(config_path: &Path, check: bool) -> Result<bool, String> {
+    let config = read_config(config_path)?;
+    let root = config_path.parent().unwrap_or(Path::new(""));
+
+    let mut schemas = vec![];
+    for input in &config.inputs {
+        schemas.extend(read_inputs(&root.join(input))?);
+    }
+
+    // Targets share one context, so schemas are only parsed and quicktype only found once.
+    let mut ctx = CodegenContext::new(&config.top_level, None);
+    if config.header {
+        ctx = ctx.with_header();
+    }
+    for (title, schema) in &schemas {
+        ctx.try_add_schema_with_title(schema, title)
+            .map_err(|e| format!("{}: {}", title, e))?;
+    }
+
+    let mut ok = true;
+    for target in &config.targets {
+        let output = root.join(&target.output);
+        let res = generate(&ctx, target, &output, check);
+        match res {
+            Ok(status) => println!("{} {}", status, output.display()),
+            Err(e) => {
+                eprintln!("error: {}: {}", output.display(), e);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn read_config(path: &Path) -> Result<Config, String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let config = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&src).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&src).map_err(|e| e.to_string())
+    };
+    config.map_err(|e| format!("invalid config {}: {}", path.display(), e))
+}
+
+/// `(fallback title, schema)` of every schema file in `path`, sorted by path.
+fn read_inputs(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let read_err = |e: std::io::Error| format!("failed to read {}: {}", path.display(), e);
+    if !path.is_dir() {
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        return Ok(vec![(
+            title,
+            std::fs::read_to_string(path).map_err(read_err)?,
+        )]);
+    }
+
+    let mut paths = std::fs::read_dir(path)
+        .map_err(read_err)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_err)?;
+    paths.sort();
+    let mut schemas = vec![];
+    for path in paths {
+        if path.is_dir() || path.extension().is_some_and(|ext| ext == "json") {
+            schemas.extend(read_inputs(&path)?);
+        }
+    }
+    Ok(schemas)
+}
+
+fn generate(
+    ctx: &CodegenContext,
+    target: &Target,
+    output: &Path,
+    check: bool,
+) -> Result<&'static str, String> {
+    let lang = target.language.clone();
+    let mut args = vec![];
+    for (name, value) in &target.options {
+        args.extend(flag(name, value)?);
+    }
+    let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+    // Clones share the resolved runner.
+    let ctx = ctx.clone().with_extra_args(&args);
+
+    if check {
+        return match ctx.check(lang, output) {
+            Ok(()) => Ok("up to date"),
+            Err(Drift::Codegen(e)) => Err(e.to_string()),
+            Err(drift) => Err(drift.to_string()),
+        };
+    }
+    match ctx.finish_to_path(lang, output) {
+        Ok(true) => Ok("wrote"),
+        Ok(false) => Ok("unchanged"),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `--name` for `true`, nothing for `false`, `--name value` otherwise, and repeated for arrays.
+fn flag(name: &str, value: &serde_json::Value) -> Result<Vec<String>, String> {
+    use serde_json::Value;
+    let arg = format!("--{}", name.trim_start_matches('-'));
+    Ok(match value {
+        Value::Bool(true) => vec![arg],
+        Value::Bool(false) | Value::Null => vec![],
+        Value::String(s) => vec![arg, s.clone()],
+        Value::Number(n) => vec![arg, n.to_string()],
+        Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                Value::Array(_) => Err(format!("option `{}` can't be a nested array", name)),
+                value => flag(name, value),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .concat(),
+        Value::Object(_) => return Err(format!("option `{}` can't be an object", name)),
+    })
+}
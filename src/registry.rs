@@ -0,0 +1,63 @@
+use super::*;
+
+#[cfg(not(any(feature = "schemars08", feature = "schemars1")))]
+compile_error!("the `registry` feature requires `schemars08` or `schemars1` for `add_type`");
+
+#[doc(hidden)]
+pub use ::inventory;
+pub use quick_type_schema_derive::QuickTypeExport;
+
+/// A type registered with `#[derive(QuickTypeExport)]`.
+#[derive(Debug)]
+pub struct RegisteredType {
+    /// The path of the type, including its module
+    pub name: &'static str,
+    /// Set with `#[quick_type_schema(group = "...")]`
+    pub groups: &'static [&'static str],
+    pub register: fn(&mut CodegenContext) -> Result<(), SchemaError>,
+}
+
+inventory::collect!(RegisteredType);
+
+/// Every registered type, sorted by name so that output does not depend on link order.
+pub fn registered_types() -> Vec<&'static RegisteredType> {
+    let mut types = inventory::iter::<RegisteredType>
+        .into_iter()
+        .collect::<Vec<_>>();
+    types.sort_by_key(|ty| ty.name);
+    types
+}
+
+impl CodegenContext {
+    pub fn add_all_registered(&mut self) {
+        self.try_add_all_registered()
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Add every type registered with `#[derive(QuickTypeExport)]`.
+    pub fn try_add_all_registered(&mut self) -> Result<(), SchemaError> {
+        self.try_add_registered(|_| true)
+    }
+
+    pub fn add_registered_group(&mut self, group: &str) {
+        self.try_add_registered_group(group)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Add the registered types in `group`.
+    pub fn try_add_registered_group(&mut self, group: &str) -> Result<(), SchemaError> {
+        self.try_add_registered(|ty| ty.groups.contains(&group))
+    }
+
+    fn try_add_registered(
+        &mut self,
+        filter: impl Fn(&RegisteredType) -> bool,
+    ) -> Result<(), SchemaError> {
+        for ty in registered_types() {
+            if filter(ty) {
+                (ty.register)(self)?;
+            }
+        }
+        Ok(())
+    }
+}
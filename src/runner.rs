@@ -0,0 +1,319 @@
+use super::*;
+use exec::Limits;
+use std::ffi::OsString;
+use std::fmt;
+use std::process::Command;
+
+/// How quicktype is invoked.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Default)]
+pub enum Runner {
+    /// Try `$QUICKTYPE_BIN`, then `quicktype`, then `npx quicktype`
+    #[default]
+    Auto,
+    /// An explicit quicktype binary
+    Path(PathBuf),
+    /// The quicktype binary named by an environment variable (ex: `QUICKTYPE_BIN`)
+    Env(String),
+    /// `npx quicktype`
+    Npx,
+    /// `pnpm dlx quicktype`
+    PnpmDlx,
+    /// `yarn dlx quicktype`
+    YarnDlx,
+    /// `bunx quicktype`
+    Bunx,
+    /// `node path/to/quicktype.js`
+    Node(PathBuf),
+}
+
+/// The environment variable checked by `Runner::Auto`.
+pub const QUICKTYPE_BIN_ENV: &str = "QUICKTYPE_BIN";
+
+/// Every runner that was tried during discovery and why it was rejected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiscoveryReport {
+    pub attempts: Vec<DiscoveryAttempt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveryAttempt {
+    /// The command used to probe the runner
+    pub command: String,
+    /// Why the runner was rejected, `None` if it was chosen
+    pub error: Option<String>,
+}
+
+impl fmt::Display for DiscoveryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for attempt in self.attempts.iter() {
+            match &attempt.error {
+                Some(e) => writeln!(f, "  `{}`: {}", attempt.command, e)?,
+                None => writeln!(f, "  `{}`: ok", attempt.command)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Probe {
+    /// Try the next candidate
+    Rejected(DiscoveryAttempt),
+    /// Stop discovery entirely
+    Aborted(CodegenError),
+}
+
+#[derive(Debug)]
+pub(crate) struct ResolvedRunner {
+    pub program: OsString,
+    pub prefix_args: Vec<OsString>,
+    pub report: DiscoveryReport,
+    version: OnceLock<String>,
+}
+
+impl ResolvedRunner {
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.prefix_args);
+        cmd
+    }
+
+    /// The output of `quicktype --version`, if it was already printed.
+    pub fn known_version(&self) -> Option<&str> {
+        self.version.get().map(|v| v.as_str())
+    }
+
+    /// The output of `quicktype --version`.
+    pub fn version(&self, limits: Limits) -> Result<&str, CodegenError> {
+        if let Some(version) = self.version.get() {
+            return Ok(version);
+        }
+        let out = exec::output(self.command().arg("--version"), limits)?;
+        if !out.status.success() {
+            return Err(CodegenError::QuicktypeFailed {
+                stderr: String::from_utf8_lossy(&out.stderr).trim().to_owned(),
+                exit_status: out.status,
+            });
+        }
+        let version = String::from_utf8_lossy(&out.stdout).trim().to_owned();
+        Ok(self.version.get_or_init(|| version))
+    }
+}
+
+impl Runner {
+    /// Identifies the quicktype this runner would use without running anything, for cache keys.
+    /// Binaries and scripts are identified by their resolved path, size, modification time
+    /// and the `package.json` they belong to.
+    /// Package runners are identified by `./node_modules/quicktype/package.json`, if quicktype is installed locally.
+    pub(crate) fn fingerprint(&self) -> String {
+        let installed = match self {
+            Runner::Auto => std::env::var_os(QUICKTYPE_BIN_ENV)
+                .map(PathBuf::from)
+                .or_else(|| Some(PathBuf::from("quicktype")))
+                .and_then(|bin| file_fingerprint(&bin))
+                .or_else(local_package),
+            Runner::Path(path) => file_fingerprint(path),
+            Runner::Env(var) => {
+                std::env::var_os(var).and_then(|path| file_fingerprint(path.as_ref()))
+            }
+            Runner::Node(script) => file_fingerprint(script),
+            Runner::Npx | Runner::PnpmDlx | Runner::YarnDlx | Runner::Bunx => local_package(),
+        };
+        format!("{:?} {}", self, installed.unwrap_or_default())
+    }
+
+    pub(crate) fn resolve(&self, limits: Limits) -> Result<ResolvedRunner, CodegenError> {
+        let mut report = DiscoveryReport::default();
+        let candidates = match self {
+            Runner::Auto => {
+                let mut candidates = vec![];
+                if std::env::var_os(QUICKTYPE_BIN_ENV).is_some() {
+                    candidates.push(Runner::Env(QUICKTYPE_BIN_ENV.to_owned()));
+                }
+                candidates.extend([Runner::Path("quicktype".into()), Runner::Npx]);
+                candidates
+            }
+            runner => vec![runner.clone()],
+        };
+
+        for candidate in candidates {
+            match candidate.probe(limits) {
+                Ok((command, resolved)) => {
+                    report.attempts.push(DiscoveryAttempt {
+                        command,
+                        error: None,
+                    });
+                    return Ok(ResolvedRunner { report, ..resolved });
+                }
+                Err(Probe::Rejected(attempt)) => report.attempts.push(attempt),
+                Err(Probe::Aborted(e)) => return Err(e),
+            }
+        }
+        Err(CodegenError::RunnerNotFound(report))
+    }
+
+    /// Package runners are probed by their own `--version` so that probing doesn't download quicktype.
+    fn probe(&self, limits: Limits) -> Result<(String, ResolvedRunner), Probe> {
+        let (program, prefix_args, probe): (OsString, Vec<OsString>, Vec<&str>) = match self {
+            Runner::Auto => unreachable!(),
+            Runner::Path(path) => (path.into(), vec![], vec![]),
+            Runner::Env(var) => match std::env::var_os(var) {
+                Some(path) if !path.is_empty() => (path, vec![], vec![]),
+                _ => {
+                    return Err(Probe::Rejected(DiscoveryAttempt {
+                        command: format!("${}", var),
+                        error: Some("environment variable is not set".to_owned()),
+                    }))
+                }
+            },
+            Runner::Npx => ("npx".into(), vec!["quicktype".into()], vec!["npx"]),
+            Runner::PnpmDlx => (
+                "pnpm".into(),
+                vec!["dlx".into(), "quicktype".into()],
+                vec!["pnpm"],
+            ),
+            Runner::YarnDlx => (
+                "yarn".into(),
+                vec!["dlx".into(), "quicktype".into()],
+                vec!["yarn"],
+            ),
+            Runner::Bunx => ("bunx".into(), vec!["quicktype".into()], vec!["bunx"]),
+            Runner::Node(script) => ("node".into(), vec![script.into()], vec![]),
+        };
+
+        let mut cmd = match probe.first() {
+            Some(tool) => Command::new(tool),
+            None => {
+                let mut cmd = Command::new(&program);
+                cmd.args(&prefix_args);
+                cmd
+            }
+        };
+        cmd.arg("--version");
+        let command = format!(
+            "{} --version",
+            std::iter::once(cmd.get_program())
+                .chain(cmd.get_args().take_while(|arg| *arg != "--version"))
+                .map(|s| s.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+
+        let out = match exec::output(&mut cmd, limits) {
+            Ok(out) => out,
+            Err(e @ (CodegenError::Cancelled | CodegenError::Timeout(_))) => {
+                return Err(Probe::Aborted(e))
+            }
+            Err(e) => {
+                return Err(Probe::Rejected(DiscoveryAttempt {
+                    command,
+                    error: Some(e.to_string()),
+                }))
+            }
+        };
+        if !out.status.success() {
+            return Err(Probe::Rejected(DiscoveryAttempt {
+                command,
+                error: Some(format!("exited with {}", out.status)),
+            }));
+        }
+
+        let version = OnceLock::new();
+        if probe.is_empty() {
+            let _ = version.set(String::from_utf8_lossy(&out.stdout).trim().to_owned());
+        }
+        Ok((
+            command,
+            ResolvedRunner {
+                program,
+                prefix_args,
+                report: DiscoveryReport::default(),
+                version,
+            },
+        ))
+    }
+}
+
+/// `program` as found in $PATH if it's a bare name.
+fn find_program(program: &Path) -> Option<PathBuf> {
+    if program.components().count() > 1 {
+        return Some(program.to_owned());
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+fn file_fingerprint(program: &Path) -> Option<String> {
+    let path = find_program(program)?.canonicalize().ok()?;
+    let meta = std::fs::metadata(&path).ok()?;
+    // npm resets modification times, so the package version is what tells releases apart.
+    let package = path
+        .ancestors()
+        .skip(1)
+        .take(4)
+        .find_map(|dir| std::fs::read_to_string(dir.join("package.json")).ok())
+        .unwrap_or_default();
+    Some(format!(
+        "{} {} {:?} {}",
+        path.display(),
+        meta.len(),
+        meta.modified().ok(),
+        package
+    ))
+}
+
+fn local_package() -> Option<String> {
+    std::fs::read_to_string(
+        std::env::current_dir()
+            .ok()?
+            .join("node_modules/quicktype/package.json"),
+    )
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp::TempDir;
+
+    #[test]
+    fn fingerprint_tracks_install() {
+        let dir = TempDir::new().unwrap();
+        let bin = dir.path().join("bin").join("quicktype");
+        std::fs::create_dir(dir.path().join("bin")).unwrap();
+        std::fs::write(&bin, "v1").unwrap();
+        let runner = Runner::Path(bin.clone());
+        let first = runner.fingerprint();
+        assert_eq!(first, runner.fingerprint());
+
+        std::fs::write(&bin, "v1.1").unwrap();
+        let second = runner.fingerprint();
+        assert_ne!(first, second);
+
+        std::fs::write(dir.path().join("package.json"), r#"{ "version": "2" }"#).unwrap();
+        assert_ne!(second, runner.fingerprint());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_version() {
+        let runner = ResolvedRunner {
+            program: "sh".into(),
+            prefix_args: ["-c", "echo broken >&2; exit 3", "sh"]
+                .map(OsString::from)
+                .to_vec(),
+            report: DiscoveryReport::default(),
+            version: OnceLock::new(),
+        };
+        let cancel = CancelHandle::default();
+        let limits = Limits {
+            timeout: None,
+            cancel: &cancel,
+        };
+        match runner.version(limits) {
+            Err(CodegenError::QuicktypeFailed { stderr, .. }) => assert_eq!(stderr, "broken"),
+            res => panic!("{:?}", res),
+        }
+        assert_eq!(runner.known_version(), None);
+    }
+}
@@ -0,0 +1,493 @@
+use super::*;
+use json::{object, JsonValue};
+use std::collections::HashMap;
+
+/// How to handle two definitions that share a name but have a different shape.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub enum ConflictStrategy {
+    /// Fail with `SchemaError::ConflictingDefinition`
+    #[default]
+    Error,
+    /// Rename the incoming definition to `Name2`, `Name3`, ...
+    NumericSuffix,
+    /// Prefix the added type with its module name (ex: `ApiStatus`).
+    /// Falls back to `NumericSuffix` for its dependencies, whose module is unknown,
+    /// and when the module is unknown or the prefixed name is taken.
+    ModulePrefix,
+}
+
+/// What quicktype is given as the top level type.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub enum TopLevel {
+    /// A wrapper type named after the base name with a property `t0`, `t1`, ... per added type
+    #[default]
+    Synthetic,
+    /// A wrapper type with a property per added type, named after the type
+    Titled,
+    /// No wrapper type, every added type is a top level type with its own converters.
+    /// Not supported by `QuicktypeServer`, which is skipped.
+    Omit,
+}
+
+/// The json schema draft of the merged schema.
+/// Added schemas may use either `definitions` or `$defs` regardless.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
+pub enum Draft {
+    #[default]
+    Draft07,
+    /// Uses `$defs` instead of `definitions`
+    Draft2019_09,
+    /// Uses `$defs` instead of `definitions`
+    Draft2020_12,
+}
+
+impl Draft {
+    pub fn uri(&self) -> &'static str {
+        match self {
+            Draft::Draft07 => "http://json-schema.org/draft-07/schema#",
+            Draft::Draft2019_09 => "https://json-schema.org/draft/2019-09/schema",
+            Draft::Draft2020_12 => "https://json-schema.org/draft/2020-12/schema",
+        }
+    }
+
+    /// The root key definitions are placed under.
+    pub fn definitions_key(&self) -> &'static str {
+        match self {
+            Draft::Draft07 => "definitions",
+            Draft::Draft2019_09 | Draft::Draft2020_12 => "$defs",
+        }
+    }
+}
+
+/// Definitions are stored with refs of this form regardless of the draft.
+const DEFINITIONS: &str = "#/definitions/";
+const DEFS: &str = "#/$defs/";
+
+/// Keys only schemars sets on the root type, ignored when comparing definitions.
+const ROOT_ONLY_KEYS: [&str; 3] = ["$schema", "title", "description"];
+
+#[derive(Debug, Clone)]
+pub struct Schema {
+    /// Every added type and its dependencies by name
+    pub(crate) definitions: JsonValue,
+    pub(crate) top_levels: Vec<String>,
+    pub(crate) raw_schemas: Vec<(String, String)>,
+    pub(crate) conflict_strategy: ConflictStrategy,
+    pub(crate) top_level: TopLevel,
+    pub(crate) converters: Option<Vec<String>>,
+    pub(crate) draft: Draft,
+}
+
+impl Schema {
+    pub fn push_schema_str(&mut self, s: &str) {
+        self.try_push_schema_str(s, None)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_push_schema_str(
+        &mut self,
+        s: &str,
+        fallback_title: Option<&str>,
+    ) -> Result<(), SchemaError> {
+        self.push_schema(s, fallback_title, None, None)
+    }
+
+    /// `type_path` is the rust path of the type the schema was derived from, if any.
+    /// `definitions_path` is where the schema keeps its definitions if not `definitions` or `$defs`,
+    /// in the form `#/components/schemas/`.
+    pub(crate) fn push_schema(
+        &mut self,
+        s: &str,
+        fallback_title: Option<&str>,
+        type_path: Option<&str>,
+        definitions_path: Option<&str>,
+    ) -> Result<(), SchemaError> {
+        let mut val = json::parse(s).map_err(|e| SchemaError::from_json(e, s))?;
+        if !val.is_object() {
+            return Err(SchemaError::NotAnObject);
+        }
+        let title = match (val["title"].as_str(), fallback_title) {
+            (Some(title), _) => title.to_owned(),
+            (None, Some(fallback)) => {
+                val["title"] = fallback.into();
+                fallback.to_owned()
+            }
+            (None, None) => return Err(SchemaError::MissingTitle),
+        };
+
+        // Nested definitions are hoisted next to the type itself.
+        let mut incoming = vec![];
+        let custom_path = definitions_path.filter(|p| *p != DEFINITIONS && *p != DEFS);
+        let mut locations = vec![vec!["definitions"], vec!["$defs"]];
+        if let Some(path) = custom_path {
+            let pointer = path.trim_start_matches("#/").trim_end_matches('/');
+            locations.push(pointer.split('/').collect());
+        }
+        for location in locations {
+            let defs = take_pointer(&mut val, &location);
+            for (k, v) in defs.entries() {
+                incoming.push((k.to_owned(), v.clone()));
+            }
+        }
+        val.remove("$schema");
+        incoming.insert(0, (title.clone(), val));
+        for (_, def) in incoming.iter_mut() {
+            map_refs(def, &|r| normalize_ref(r, &title, custom_path));
+        }
+
+        let module = type_path.and_then(module_prefix);
+        let renames = self.resolve_conflicts(&incoming, &title, module.as_deref())?;
+
+        self.raw_schemas.push((title.clone(), s.to_owned()));
+
+        for (name, mut def) in incoming {
+            rename_refs(&mut def, &renames);
+            let name = match renames.get(&name) {
+                Some(new_name) => {
+                    if def["title"].as_str() == Some(name.as_str()) {
+                        def["title"] = new_name.as_str().into();
+                    }
+                    new_name.clone()
+                }
+                None => name,
+            };
+            // The same definition may arrive as a root with a title and nested without one.
+            let existing = &mut self.definitions[name];
+            if existing.is_null() {
+                *existing = def;
+            } else {
+                for key in ROOT_ONLY_KEYS {
+                    if existing[key].is_null() && !def[key].is_null() {
+                        existing[key] = def.remove(key);
+                    }
+                }
+            }
+        }
+
+        let title = renames.get(&title).unwrap_or(&title);
+        self.top_levels.push(title.clone());
+        Ok(())
+    }
+
+    /// The schema given to quicktype.
+    pub(crate) fn to_json(&self) -> String {
+        let mut val = object! {
+            "$schema": self.draft.uri(),
+            "type": "object",
+        };
+        val[self.draft.definitions_key()] = self.definitions.clone();
+        for (i, name) in self.top_levels.iter().enumerate() {
+            if !self.is_top_level(name) {
+                continue;
+            }
+            let key = match self.top_level {
+                TopLevel::Synthetic => format!("t{}", i),
+                TopLevel::Titled => name.clone(),
+                TopLevel::Omit => continue,
+            };
+            val["properties"][key] = object! {
+                "$ref": format!("{}{}", DEFINITIONS, name)
+            };
+        }
+        if self.draft.definitions_key() == "$defs" {
+            map_refs(&mut val, &|r| {
+                r.strip_prefix(DEFINITIONS)
+                    .map(|path| format!("{}{}", DEFS, path))
+            });
+        }
+        val.to_string()
+    }
+
+    /// The types quicktype generates converters for when the wrapper type is omitted.
+    pub(crate) fn omitted_top_levels(&self) -> Vec<&str> {
+        match self.top_level {
+            TopLevel::Omit => self
+                .top_levels
+                .iter()
+                .filter(|name| self.is_top_level(name))
+                .map(|name| name.as_str())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Names passed to `with_converters` that aren't the title of an added type.
+    pub(crate) fn unknown_converters(&self) -> Vec<String> {
+        self.converters
+            .iter()
+            .flatten()
+            .filter(|c| !self.top_levels.contains(c))
+            .cloned()
+            .collect()
+    }
+
+    fn is_top_level(&self, name: &str) -> bool {
+        self.converters
+            .as_ref()
+            .is_none_or(|converters| converters.iter().any(|c| c == name))
+    }
+
+    /// Find a new name for every incoming definition that clashes with an existing one.
+    /// Renaming a definition changes the refs of its dependents, so repeat until nothing changes.
+    fn resolve_conflicts(
+        &self,
+        incoming: &[(String, JsonValue)],
+        root: &str,
+        module: Option<&str>,
+    ) -> Result<HashMap<String, String>, SchemaError> {
+        let mut renames = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (name, def) in incoming {
+                if renames.contains_key(name) {
+                    continue;
+                }
+                let mut def = def.clone();
+                rename_refs(&mut def, &renames);
+                if self.is_free(name, &def) {
+                    continue;
+                }
+
+                let prefixed = match (self.conflict_strategy, module) {
+                    (ConflictStrategy::Error, _) => {
+                        return Err(SchemaError::ConflictingDefinition { name: name.clone() })
+                    }
+                    (ConflictStrategy::ModulePrefix, Some(module)) if name == root => {
+                        Some(format!("{}{}", module, name))
+                    }
+                    _ => None,
+                };
+                let taken = |candidate: &str| {
+                    !self.is_free(candidate, &def)
+                        || renames.values().any(|v| v == candidate)
+                        || incoming.iter().any(|(n, _)| n == candidate)
+                };
+                let new_name = prefixed
+                    .into_iter()
+                    .chain((2..).map(|n| format!("{}{}", name, n)))
+                    .find(|candidate| !taken(candidate))
+                    .unwrap();
+
+                renames.insert(name.clone(), new_name);
+                changed = true;
+            }
+            if !changed {
+                return Ok(renames);
+            }
+        }
+    }
+
+    fn is_free(&self, name: &str, def: &JsonValue) -> bool {
+        let existing = &self.definitions[name];
+        existing.is_null() || same_shape(existing, def)
+    }
+}
+
+fn same_shape(a: &JsonValue, b: &JsonValue) -> bool {
+    let strip = |val: &JsonValue| {
+        let mut val = val.clone();
+        for key in ROOT_ONLY_KEYS {
+            val.remove(key);
+        }
+        val
+    };
+    strip(a) == strip(b)
+}
+
+fn rename_refs(val: &mut JsonValue, renames: &HashMap<String, String>) {
+    if renames.is_empty() {
+        return;
+    }
+    map_refs(val, &|r| {
+        let path = r.strip_prefix(DEFINITIONS)?;
+        let (name, rest) = path.split_once('/').unwrap_or((path, ""));
+        let sep = if rest.is_empty() { "" } else { "/" };
+        renames
+            .get(name)
+            .map(|new_name| format!("{}{}{}{}", DEFINITIONS, new_name, sep, rest))
+    });
+}
+
+/// `$defs` refs become `definitions` refs, and refs to the root become refs to its definition.
+fn normalize_ref(r: &str, title: &str, custom_path: Option<&str>) -> Option<String> {
+    if r == "#" {
+        return Some(format!("{}{}", DEFINITIONS, title));
+    }
+    r.strip_prefix(DEFS)
+        .or_else(|| r.strip_prefix(custom_path?))
+        .map(|path| format!("{}{}", DEFINITIONS, path))
+}
+
+/// Remove and return the value at `pointer`, or null if there is none.
+fn take_pointer(val: &mut JsonValue, pointer: &[&str]) -> JsonValue {
+    match pointer {
+        [] => JsonValue::Null,
+        [key] => val.remove(key),
+        [key, rest @ ..] => {
+            if !val[*key].is_object() {
+                return JsonValue::Null;
+            }
+            let taken = take_pointer(&mut val[*key], rest);
+            if val[*key].is_empty() {
+                val.remove(key);
+            }
+            taken
+        }
+    }
+}
+
+/// Replace every `$ref` for which `f` returns `Some`.
+fn map_refs(val: &mut JsonValue, f: &dyn Fn(&str) -> Option<String>) {
+    match val {
+        JsonValue::Object(obj) => {
+            for (k, v) in obj.iter_mut() {
+                if k == "$ref" {
+                    if let Some(new_ref) = v.as_str().and_then(f) {
+                        *v = new_ref.into();
+                    }
+                } else {
+                    map_refs(v, f);
+                }
+            }
+        }
+        JsonValue::Array(arr) => {
+            for v in arr.iter_mut() {
+                map_refs(v, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `my_crate::api_v2::Status<T>` -> `ApiV2`
+fn module_prefix(type_path: &str) -> Option<String> {
+    let path = type_path.split('<').next().unwrap_or(type_path);
+    let (module, _) = path.rsplit_once("::")?;
+    let module = module.rsplit("::").next().unwrap_or(module);
+    let prefix = module
+        .split('_')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<String>();
+    (!prefix.is_empty()).then_some(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(conflict_strategy: ConflictStrategy) -> Schema {
+        Schema {
+            definitions: object! {},
+            top_levels: vec![],
+            raw_schemas: vec![],
+            conflict_strategy,
+            top_level: TopLevel::default(),
+            converters: None,
+            draft: Draft::default(),
+        }
+    }
+
+    const A: &str = r#"{
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "A",
+        "type": "object",
+        "properties": { "x": { "type": "integer" } }
+    }"#;
+
+    const B: &str = r##"{
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "B",
+        "type": "object",
+        "properties": { "a": { "$ref": "#/definitions/A" } },
+        "definitions": {
+            "A": { "type": "object", "properties": { "x": { "type": "integer" } } }
+        }
+    }"##;
+
+    const C: &str = r##"{
+        "title": "C",
+        "type": "object",
+        "properties": { "a": { "$ref": "#/definitions/A" } },
+        "definitions": {
+            "A": { "type": "object", "properties": { "y": { "type": "string" } } }
+        }
+    }"##;
+
+    #[test]
+    fn nested_then_root() {
+        let mut schema = schema(ConflictStrategy::Error);
+        schema.push_schema(B, None, None, None).unwrap();
+        schema.push_schema(A, None, None, None).unwrap();
+        assert_eq!(schema.definitions["A"]["title"], "A");
+        assert_eq!(schema.top_levels, ["B", "A"]);
+    }
+
+    #[test]
+    fn root_then_nested() {
+        let mut schema = schema(ConflictStrategy::Error);
+        schema.push_schema(A, None, None, None).unwrap();
+        schema.push_schema(B, None, None, None).unwrap();
+        assert_eq!(schema.definitions["A"]["title"], "A");
+        assert!(schema.definitions["A"]["$schema"].is_null());
+    }
+
+    #[test]
+    fn conflict() {
+        let mut schema = schema(ConflictStrategy::Error);
+        schema.push_schema(B, None, None, None).unwrap();
+        assert_eq!(
+            schema.push_schema(C, None, None, None),
+            Err(SchemaError::ConflictingDefinition {
+                name: "A".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn rename_rewrites_refs() {
+        let mut schema = schema(ConflictStrategy::NumericSuffix);
+        schema.push_schema(B, None, None, None).unwrap();
+        schema.push_schema(C, None, None, None).unwrap();
+        assert_eq!(
+            schema.definitions["A"]["properties"]["x"]["type"],
+            "integer"
+        );
+        assert_eq!(
+            schema.definitions["A2"]["properties"]["y"]["type"],
+            "string"
+        );
+        assert_eq!(
+            schema.definitions["C"]["properties"]["a"]["$ref"],
+            "#/definitions/A2"
+        );
+        assert_eq!(
+            schema.definitions["B"]["properties"]["a"]["$ref"],
+            "#/definitions/A"
+        );
+    }
+
+    #[test]
+    fn module_prefix_only_renames_root() {
+        let mut schema = schema(ConflictStrategy::ModulePrefix);
+        schema.push_schema(B, None, None, None).unwrap();
+        schema
+            .push_schema(
+                &C.replace("\"C\"", "\"B\""),
+                None,
+                Some("my_crate::api::B"),
+                None,
+            )
+            .unwrap();
+        assert!(!schema.definitions["ApiB"].is_null());
+        assert!(!schema.definitions["A2"].is_null());
+        assert!(schema.definitions["ApiA"].is_null());
+        assert_eq!(schema.top_levels, ["B", "ApiB"]);
+    }
+}
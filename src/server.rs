@@ -0,0 +1,279 @@
+use super::*;
+use exec::{Limits, POLL_INTERVAL};
+use json::{object, JsonValue};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Instant;
+
+const DRIVER: &str = include_str!("worker.js");
+
+/// A long-lived node process running `quicktype-core`.
+///
+/// Spawning quicktype takes seconds, mostly spent starting node.
+/// The server pays that cost once, so repeated `finish` calls only take milliseconds.
+/// Requires `node` in $PATH and `quicktype-core` installed either globally, in `./node_modules`, or in $NODE_PATH.
+#[derive(Debug)]
+pub struct QuicktypeServer {
+    worker: Mutex<Worker>,
+    version: Option<String>,
+}
+
+#[derive(Debug)]
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines of stdout, read on another thread so that waiting for them can time out
+    lines: Receiver<String>,
+    next_id: u64,
+    dead: bool,
+    _dir: TempDir,
+}
+
+impl QuicktypeServer {
+    pub fn start() -> Result<Self, CodegenError> {
+        let cancel = CancelHandle::default();
+        Self::start_with_limits(Limits {
+            timeout: None,
+            cancel: &cancel,
+        })
+    }
+
+    /// `start`, giving up when loading `quicktype-core` exceeds `limits`.
+    pub(crate) fn start_with_limits(limits: Limits) -> Result<Self, CodegenError> {
+        let dir = TempDir::new()?;
+        let driver_path = dir.path().join("driver.js");
+        std::fs::write(&driver_path, DRIVER)?;
+
+        let child = Command::new("node")
+            .arg(&driver_path)
+            .env("NODE_PATH", node_path(limits))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| CodegenError::WorkerUnavailable(format!("failed to start node: {}", e)))?;
+        let mut worker = Worker::new(child, dir);
+        let ready = worker.read_message(Instant::now(), limits)?;
+        if ready["ready"].as_bool() != Some(true) {
+            return Err(CodegenError::WorkerUnavailable(
+                ready["error"]
+                    .as_str()
+                    .unwrap_or("quicktype-core could not be loaded")
+                    .to_owned(),
+            ));
+        }
+
+        Ok(QuicktypeServer {
+            version: ready["version"].as_str().map(|s| s.to_owned()),
+            worker: Mutex::new(worker),
+        })
+    }
+
+    /// The version of `quicktype-core` loaded by the server, if known.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Generate code with the same arguments that would be passed to the quicktype cli.
+    /// When `limits` are exceeded, the worker is killed and can't be used again.
+    pub(crate) fn generate(
+        &self,
+        schema: &str,
+        base_name: &str,
+        args: &[String],
+        limits: Limits,
+    ) -> Result<String, CodegenError> {
+        let mut worker = self.worker.lock().unwrap_or_else(|e| e.into_inner());
+        if worker.dead {
+            return Err(CodegenError::WorkerUnavailable(
+                "worker has exited".to_owned(),
+            ));
+        }
+
+        if limits.cancel.is_cancelled() {
+            return Err(CodegenError::Cancelled);
+        }
+
+        let res = worker.request(schema, base_name, args, limits);
+        if let Err(
+            CodegenError::WorkerUnavailable(_)
+            | CodegenError::Io(_)
+            | CodegenError::Timeout(_)
+            | CodegenError::Cancelled,
+        ) = res
+        {
+            worker.dead = true;
+            let _ = worker.child.kill();
+        }
+        res
+    }
+}
+
+impl Worker {
+    fn new(mut child: Child, dir: TempDir) -> Self {
+        let stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            match stdout.read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Worker {
+            child,
+            stdin,
+            lines,
+            next_id: 0,
+            dead: false,
+            _dir: dir,
+        }
+    }
+
+    fn request(
+        &mut self,
+        schema: &str,
+        base_name: &str,
+        args: &[String],
+        limits: Limits,
+    ) -> Result<String, CodegenError> {
+        let start = Instant::now();
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut lang = JsonValue::Null;
+        let mut top_level = JsonValue::from(base_name);
+        let mut renderer_options = JsonValue::new_object();
+        let mut args = args.iter().peekable();
+        while let Some(arg) = args.next() {
+            let value = args.next_if(|next| !next.starts_with('-'));
+            match (arg.as_str(), value) {
+                ("-l" | "--lang", Some(value)) => lang = value.as_str().into(),
+                ("-t" | "--top-level", Some(value)) => top_level = value.as_str().into(),
+                ("--quiet", _) => {}
+                (flag, value) => {
+                    let name = flag.trim_start_matches('-');
+                    renderer_options[name] = match value {
+                        Some(value) => value.as_str().into(),
+                        None => true.into(),
+                    };
+                }
+            }
+        }
+
+        let req = object! {
+            "id": id,
+            "lang": lang,
+            "topLevel": top_level,
+            "schema": schema,
+            "rendererOptions": renderer_options,
+        };
+        writeln!(self.stdin, "{}", req.dump())?;
+        self.stdin.flush()?;
+
+        let res = self.read_message(start, limits)?;
+        if res["id"].as_u64() != Some(id) {
+            return Err(CodegenError::WorkerUnavailable(
+                "worker responded out of order".to_owned(),
+            ));
+        }
+        match (res["code"].as_str(), res["error"].as_str()) {
+            (Some(code), _) => Ok(code.to_owned()),
+            (None, error) => Err(CodegenError::WorkerFailed(
+                error.unwrap_or("unknown error").to_owned(),
+            )),
+        }
+    }
+
+    /// Wait for the next line of output until `limits` are exceeded, counting from `start`.
+    fn read_message(&mut self, start: Instant, limits: Limits) -> Result<JsonValue, CodegenError> {
+        let line = loop {
+            match self.lines.recv_timeout(POLL_INTERVAL) {
+                Ok(line) => break line,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(CodegenError::WorkerUnavailable(
+                        "worker has exited".to_owned(),
+                    ))
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            match limits.timeout {
+                _ if limits.cancel.is_cancelled() => return Err(CodegenError::Cancelled),
+                Some(timeout) if start.elapsed() >= timeout => {
+                    return Err(CodegenError::Timeout(timeout))
+                }
+                _ => {}
+            }
+        };
+        json::parse(&line).map_err(|e| CodegenError::WorkerUnavailable(e.to_string()))
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// $NODE_PATH, `./node_modules`, then the global npm modules.
+fn node_path(limits: Limits) -> std::ffi::OsString {
+    let mut paths = std::env::var_os("NODE_PATH")
+        .map(|p| std::env::split_paths(&p).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if let Ok(cwd) = std::env::current_dir() {
+        paths.push(cwd.join("node_modules"));
+    }
+    if let Ok(out) = exec::output(Command::new("npm").args(["root", "-g"]), limits) {
+        if out.status.success() {
+            let root = String::from_utf8_lossy(&out.stdout).trim().to_owned();
+            if !root.is_empty() {
+                paths.push(PathBuf::from(root));
+            }
+        }
+    }
+    std::env::join_paths(paths).unwrap_or_default()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn hung_worker_times_out() {
+        let child = Command::new("sleep")
+            .arg("10")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let server = QuicktypeServer {
+            worker: Mutex::new(Worker::new(child, TempDir::new().unwrap())),
+            version: None,
+        };
+        let cancel = CancelHandle::default();
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(100)),
+            cancel: &cancel,
+        };
+
+        let start = Instant::now();
+        let res = server.generate("{}", "TopLevel", &[], limits);
+        assert!(matches!(res, Err(CodegenError::Timeout(_))), "{:?}", res);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let res = server.generate("{}", "TopLevel", &[], limits);
+        assert!(
+            matches!(res, Err(CodegenError::WorkerUnavailable(_))),
+            "{:?}",
+            res
+        );
+    }
+}
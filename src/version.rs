@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// A quicktype release, as printed by `quicktype --version`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct QuicktypeVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl QuicktypeVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        QuicktypeVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Find the first `major.minor[.patch]` in `s`.
+    /// Accepts `quicktype version 23.0.170`, `v23.0.170`, or `23.0.170`.
+    /// Pre-release and build suffixes such as `-beta.1` or `+abc` are ignored.
+    pub fn parse(s: &str) -> Option<Self> {
+        s.split(|c: char| c.is_whitespace() || c == 'v')
+            .find_map(|word| {
+                let word = word.split(['-', '+']).next()?;
+                let mut nums = word.split('.').map(|n| n.parse::<u32>().ok());
+                let major = nums.next()??;
+                let minor = nums.next()??;
+                let patch = nums.next().unwrap_or(Some(0))?;
+                Some(QuicktypeVersion::new(major, minor, patch))
+            })
+    }
+}
+
+impl fmt::Display for QuicktypeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let v = QuicktypeVersion::new;
+        for (s, expected) in [
+            ("quicktype version 23.0.170", Some(v(23, 0, 170))),
+            ("v23.0.170", Some(v(23, 0, 170))),
+            ("23.1", Some(v(23, 1, 0))),
+            ("quicktype version 23.0.170-beta.1", Some(v(23, 0, 170))),
+            ("23.0.170+build.5", Some(v(23, 0, 170))),
+            ("quicktype-core 23.2.6", Some(v(23, 2, 6))),
+            ("quicktype version unknown", None),
+            ("23", None),
+        ] {
+            assert_eq!(QuicktypeVersion::parse(s), expected, "{}", s);
+        }
+        assert!(v(23, 0, 170) < v(23, 1, 0));
+    }
+}
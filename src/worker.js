@@ -0,0 +1,56 @@
+// Driver for `QuicktypeServer`.
+// Reads one json request per line from stdin and writes one json response per line to stdout.
+//
+// request:  { "id": 0, "lang": "rust", "topLevel": "Name", "schema": "{...}", "rendererOptions": {} }
+// response: { "id": 0, "code": "..." } or { "id": 0, "error": "..." }
+
+const readline = require("readline");
+
+function send(msg) {
+    process.stdout.write(JSON.stringify(msg) + "\n");
+}
+
+let core;
+try {
+    core = require("quicktype-core");
+} catch (e) {
+    send({ ready: false, error: String(e) });
+    process.exit(1);
+}
+const { quicktype, InputData, JSONSchemaInput, FetchingJSONSchemaStore } = core;
+
+let version = null;
+try {
+    version = require("quicktype-core/package.json").version;
+} catch (e) {}
+
+async function generate(req) {
+    const schemaInput = new JSONSchemaInput(new FetchingJSONSchemaStore());
+    await schemaInput.addSource({ name: req.topLevel, schema: req.schema });
+
+    const inputData = new InputData();
+    inputData.addInput(schemaInput);
+
+    const result = await quicktype({
+        inputData,
+        lang: req.lang,
+        rendererOptions: req.rendererOptions,
+    });
+    return result.lines.join("\n");
+}
+
+let queue = Promise.resolve();
+readline.createInterface({ input: process.stdin }).on("line", (line) => {
+    queue = queue.then(async () => {
+        let id = null;
+        try {
+            const req = JSON.parse(line);
+            id = req.id;
+            send({ id, code: await generate(req) });
+        } catch (e) {
+            send({ id, error: String((e && e.stack) || e) });
+        }
+    });
+});
+
+send({ ready: true, version });
@@ -0,0 +1,93 @@
+use super::*;
+use std::io::ErrorKind;
+
+impl CodegenContext {
+    /// Write generated code to `path`, creating parent directories.
+    /// The file is only touched if its contents changed, so downstream incremental builds aren't invalidated.
+    /// Returns whether the file was written.
+    pub fn finish_to_path(
+        &self,
+        lang: Language,
+        path: impl AsRef<Path>,
+    ) -> Result<bool, CodegenError> {
+        let code = self.try_finish(lang)?;
+        Ok(write_if_changed(path.as_ref(), &code)?)
+    }
+
+    /// Write every generated file into `dir`, see `finish_files` and `finish_to_path`.
+    /// Single file output is named `{base_name}.{extension}`.
+    /// Returns whether any file was written.
+    pub fn finish_to_dir(
+        &self,
+        lang: Language,
+        dir: impl AsRef<Path>,
+    ) -> Result<bool, CodegenError> {
+        let mut changed = false;
+        for (path, code) in self.finish_files(lang)? {
+            changed |= write_if_changed(&dir.as_ref().join(path), &code)?;
+        }
+        Ok(changed)
+    }
+}
+
+/// Atomically replace `path` with `contents` (write a temporary file, then rename) unless it's already up to date.
+pub(crate) fn write_if_changed(path: &Path, contents: &str) -> std::io::Result<bool> {
+    match std::fs::read(path) {
+        Ok(existing) if existing == contents.as_bytes() => return Ok(false),
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidInput, "path has no file name"))?;
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let tmp = parent.join(format!(
+        ".{}.{}-{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&tmp, contents)?;
+    if let Err(e) = std::fs::rename(&tmp, path) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp::TempDir;
+
+    #[test]
+    fn writes_only_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a").join("b").join("bindings.rs");
+        assert!(write_if_changed(&path, "one").unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one");
+
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        assert!(!write_if_changed(&path, "one").unwrap());
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().modified().unwrap(),
+            modified
+        );
+
+        assert!(write_if_changed(&path, "two").unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two");
+        // No temporary files are left behind.
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+    }
+}
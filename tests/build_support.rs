@@ -0,0 +1,79 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Stub;
+use quick_type_schema::{build_support::BuildCodegen, *};
+use std::path::PathBuf;
+
+fn out_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!(
+        "build-support-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn generate(build: &BuildCodegen) -> Option<PathBuf> {
+    build.generate(Language::Typescript(Default::default()), "api.ts")
+}
+
+#[test]
+fn skips_unchanged() {
+    let dir = out_dir("skip");
+    let stub = Stub::new();
+    let build = BuildCodegen::new(stub.context("A")).with_out_dir(&dir);
+
+    let path = generate(&build).unwrap();
+    assert_eq!(path, dir.join("api.ts"));
+    assert!(std::fs::read_to_string(&path)
+        .unwrap()
+        .starts_with("// typescript\n"));
+
+    // quicktype isn't run again, so the edit survives.
+    std::fs::write(&path, "edited").unwrap();
+    assert_eq!(generate(&build).unwrap(), path);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "edited");
+
+    let mut build = build;
+    build
+        .context_mut()
+        .add_schema(r#"{ "title": "B", "type": "integer" }"#);
+    generate(&build).unwrap();
+    assert_ne!(std::fs::read_to_string(&path).unwrap(), "edited");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn runner_change_regenerates() {
+    let dir = out_dir("runner");
+    let (stub, other) = (Stub::new(), Stub::new());
+    let path = generate(&BuildCodegen::new(stub.context("A")).with_out_dir(&dir)).unwrap();
+    std::fs::write(&path, "edited").unwrap();
+
+    let ctx = stub.context("A").with_runner(other.runner());
+    generate(&BuildCodegen::new(ctx).with_out_dir(&dir)).unwrap();
+    assert_ne!(std::fs::read_to_string(&path).unwrap(), "edited");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failure_keeps_previous_file() {
+    let dir = out_dir("failure");
+    let stub = Stub::new();
+    let path = generate(&BuildCodegen::new(stub.context("A")).with_out_dir(&dir)).unwrap();
+    let generated = std::fs::read_to_string(&path).unwrap();
+
+    // A changed schema with a missing quicktype is reported as a cargo warning.
+    let ctx = stub
+        .context("B")
+        .with_runner(Runner::Path(dir.join("missing-quicktype")));
+    assert_eq!(generate(&BuildCodegen::new(ctx).with_out_dir(&dir)), None);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), generated);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,50 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Stub;
+use quick_type_schema::*;
+use std::path::PathBuf;
+
+fn lang() -> Language {
+    Language::Typescript(Default::default())
+}
+
+#[test]
+fn check() {
+    let dir =
+        PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("check-{}", std::process::id()));
+    let path = dir.join("api.ts");
+    let stub = Stub::new();
+    let ctx = stub.context("A");
+
+    match ctx.check(lang(), &path) {
+        Err(Drift::Missing(missing)) => assert_eq!(missing, path),
+        res => panic!("{:?}", res),
+    }
+
+    ctx.finish_to_path(lang(), &path).unwrap();
+    ctx.check(lang(), &path).unwrap();
+
+    let code = std::fs::read_to_string(&path).unwrap();
+    let (first, schema) = code.split_once('\n').unwrap();
+    std::fs::write(&path, format!("{}\nstale\n", first)).unwrap();
+    match ctx.check(lang(), &path) {
+        Err(Drift::Changed {
+            path: changed,
+            diff,
+        }) => {
+            assert_eq!(changed, path);
+            let display = path.display();
+            assert_eq!(
+                diff,
+                format!(
+                    "--- {display}\n+++ {display} (generated)\n@@ -1,2 +1,2 @@\n {first}\n-stale\n+{schema}\n\\ No newline at end of file\n"
+                )
+            );
+        }
+        res => panic!("{:?}", res),
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,126 @@
+#![cfg(all(unix, feature = "cli"))]
+
+mod common;
+
+use common::Stub;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const CONFIG: &str = r#"
+top_level = "Api"
+inputs = ["schemas/"]
+
+[[targets]]
+language = "typescript"
+output = "out/api.ts"
+options = { acronym-style = "camel", nice-property-names = true }
+
+[[targets]]
+language = "rust"
+output = "out/api.rs"
+"#;
+
+/// A project with `config` and a schema named `Request`, removed on drop.
+struct Project {
+    dir: PathBuf,
+    stub: Stub,
+}
+
+impl Project {
+    fn new(name: &str, config: &str) -> Self {
+        let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!(
+            "cli-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("schemas")).unwrap();
+        std::fs::write(dir.join("quick-type-schema.toml"), config).unwrap();
+        std::fs::write(dir.join("schemas/Request.json"), r#"{ "type": "string" }"#).unwrap();
+        Project {
+            dir,
+            stub: Stub::new(),
+        }
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_quick-type-schema"))
+            .args(args)
+            .arg(self.dir.join("quick-type-schema.toml"))
+            .env("QUICKTYPE_BIN", self.stub.bin())
+            .env("STUB_ARGS", self.path("args"))
+            .output()
+            .unwrap()
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        self.dir.join(path)
+    }
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn generates_targets() {
+    let project = Project::new("generate", CONFIG);
+    let out = project.run(&[]);
+    assert!(out.status.success(), "{:?}", out);
+    assert!(read(&project.path("out/api.ts")).starts_with("// typescript\n"));
+    assert!(read(&project.path("out/api.rs")).starts_with("// rust\n"));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("wrote"), "{}", stdout);
+
+    // Options are added to the language's arguments rather than replacing them.
+    let args = read(&project.path("args"));
+    let typescript = args.lines().find(|args| args.contains("-l typescript"));
+    let typescript = typescript.unwrap_or_else(|| panic!("{}", args));
+    assert!(
+        typescript.ends_with("--acronym-style camel --nice-property-names"),
+        "{}",
+        typescript
+    );
+
+    let out = project.run(&[]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(stdout.matches("unchanged").count(), 2, "{}", stdout);
+}
+
+#[test]
+fn check() {
+    let project = Project::new("check", CONFIG);
+    assert!(!project.run(&["--check"]).status.success());
+
+    assert!(project.run(&[]).status.success());
+    let out = project.run(&["--check"]);
+    assert!(out.status.success(), "{:?}", out);
+
+    std::fs::write(project.path("out/api.rs"), "// edited\n").unwrap();
+    let out = project.run(&["--check"]);
+    assert!(!out.status.success());
+    assert_eq!(read(&project.path("out/api.rs")), "// edited\n");
+}
+
+#[test]
+fn invalid_option() {
+    let config = CONFIG.replace(r#"acronym-style = "camel""#, "acronym-style = { a = 1 }");
+    let project = Project::new("invalid", &config);
+    let out = project.run(&[]);
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("option `acronym-style` can't be an object"),
+        "{}",
+        stderr
+    );
+    // Other targets are still generated.
+    assert!(project.path("out/api.rs").exists());
+}
@@ -0,0 +1,147 @@
+#![allow(dead_code)]
+
+use quick_type_schema::*;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Writes `// <language>` and the input schema back out as the "generated" code.
+/// Given a directory of `.schema` files, writes each file name and its contents on a line instead of the schema.
+/// Arguments are appended to `$STUB_ARGS` when it's set.
+const STUB_QUICKTYPE: &str = r#"#!/bin/sh
+if [ "$1" = "--version" ]; then
+    echo "quicktype version 23.0.0"
+    exit 0
+fi
+if [ -n "$STUB_ARGS" ]; then
+    echo "$*" >> "$STUB_ARGS"
+fi
+out=""
+schema=""
+lang=""
+while [ $# -gt 0 ]; do
+    case "$1" in
+        -l) lang="$2"; shift 2 ;;
+        -o) out="$2"; shift 2 ;;
+        --src-lang) schema="$3"; shift 3 ;;
+        *) shift ;;
+    esac
+done
+echo "// $lang" > "$out"
+if [ -d "$schema" ]; then
+    for f in "$schema"/*.schema; do
+        echo "$(basename "$f") $(cat "$f")"
+    done >> "$out"
+else
+    cat "$schema" >> "$out"
+fi
+"#;
+
+/// A stub quicktype in its own directory, removed on drop.
+pub struct Stub {
+    dir: PathBuf,
+}
+
+impl Stub {
+    pub fn new() -> Self {
+        Stub::with_script(STUB_QUICKTYPE)
+    }
+
+    /// A stub running `script` instead.
+    pub fn with_script(script: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "quick-type-stub-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let bin = dir.join("quicktype");
+        std::fs::write(&bin, script).unwrap();
+        std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+        Stub { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn bin(&self) -> PathBuf {
+        self.dir.join("quicktype")
+    }
+
+    pub fn runner(&self) -> Runner {
+        Runner::Path(self.bin())
+    }
+
+    pub fn context(&self, title: &str) -> CodegenContext {
+        let mut ctx = CodegenContext::new("TopLevel", None).with_runner(self.runner());
+        ctx.add_schema(&format!(r#"{{ "title": "{}", "type": "string" }}"#, title));
+        ctx
+    }
+}
+
+/// The schema the stub was given, parsed from the code it generated.
+pub fn schema_of(code: &str) -> serde_json::Value {
+    let (_, schema) = code.split_once('\n').unwrap();
+    serde_json::from_str(schema).unwrap()
+}
+
+impl Drop for Stub {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Checks the registry of a test binary registering `First { shared: Shared }` in group `a`
+/// and `Second { shared: Shared, first: First }` in group `b`, from module `module`.
+#[cfg(feature = "registry")]
+pub fn check_registry(module: &str) {
+    let names = registered_types()
+        .into_iter()
+        .map(|ty| ty.name)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [format!("{}::First", module), format!("{}::Second", module)]
+    );
+
+    let stub = Stub::new();
+    let lang = Language::Typescript(Default::default());
+    let definitions = |ctx: &CodegenContext| {
+        let schema = schema_of(&ctx.try_finish(lang.clone()).unwrap());
+        let names = schema["definitions"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        (names, schema["properties"].clone())
+    };
+
+    let mut ctx = CodegenContext::new("TopLevel", None).with_runner(stub.runner());
+    ctx.try_add_all_registered().unwrap();
+    let (names, properties) = definitions(&ctx);
+    assert_eq!(names, ["First", "Second", "Shared"]);
+    assert_eq!(
+        properties,
+        serde_json::json!({
+            "t0": { "$ref": "#/definitions/First" },
+            "t1": { "$ref": "#/definitions/Second" },
+        })
+    );
+
+    let mut ctx = CodegenContext::new("TopLevel", None).with_runner(stub.runner());
+    ctx.try_add_registered_group("a").unwrap();
+    let (names, properties) = definitions(&ctx);
+    assert_eq!(names, ["First", "Shared"]);
+    assert_eq!(
+        properties,
+        serde_json::json!({ "t0": { "$ref": "#/definitions/First" } })
+    );
+
+    let mut ctx = CodegenContext::new("TopLevel", None).with_runner(stub.runner());
+    ctx.try_add_registered_group("b").unwrap();
+    ctx.try_add_registered_group("a").unwrap();
+    assert_eq!(definitions(&ctx).0, ["First", "Second", "Shared"]);
+}
@@ -0,0 +1,44 @@
+#![cfg(unix)]
+//! The only test in this binary, since it points `TMPDIR` at its own directory
+//! to check that every temporary directory is removed.
+
+mod common;
+
+use common::Stub;
+use quick_type_schema::*;
+use std::path::PathBuf;
+
+#[test]
+fn concurrent_finish() {
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join(format!("concurrent-finish-{}", std::process::id()));
+    std::fs::create_dir_all(&tmp).unwrap();
+    std::env::set_var("TMPDIR", &tmp);
+
+    let stub = Stub::new();
+    let shared = stub.context("Shared");
+    std::thread::scope(|scope| {
+        for i in 0..32 {
+            let (stub, shared) = (&stub, &shared);
+            scope.spawn(move || {
+                let lang = Language::Typescript(Default::default());
+                if i % 2 == 0 {
+                    let title = format!("Own{}", i);
+                    let out = stub.context(&title).finish(lang);
+                    assert!(out.contains(&format!("\"{}\"", title)), "{}", out);
+                } else {
+                    let out = shared.finish(lang);
+                    assert!(out.contains("\"Shared\""), "{}", out);
+                }
+            });
+        }
+    });
+    drop(stub);
+
+    let leftover = std::fs::read_dir(&tmp)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect::<Vec<_>>();
+    assert!(leftover.is_empty(), "{:?}", leftover);
+    std::fs::remove_dir(&tmp).unwrap();
+}
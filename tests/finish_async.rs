@@ -0,0 +1,54 @@
+#![cfg(all(unix, feature = "tokio"))]
+
+mod common;
+
+use common::Stub;
+use quick_type_schema::*;
+use std::time::{Duration, Instant};
+
+/// Answers `--version`, then starts a `sleep` that outlives the script unless its process group is killed.
+const SLOW_QUICKTYPE: &str = r#"#!/bin/sh
+if [ "$1" = "--version" ]; then
+    echo "quicktype version 23.0.0"
+    exit 0
+fi
+sleep 30 &
+echo $! > "$(dirname "$0")/sleep.pid"
+wait
+"#;
+
+fn lang() -> Language {
+    Language::Typescript(Default::default())
+}
+
+#[tokio::test]
+async fn finish_async() {
+    let stub = Stub::new();
+    let code = stub.context("A").finish_async(lang()).await.unwrap();
+    assert!(code.starts_with("// typescript\n"), "{}", code);
+    assert_eq!(
+        common::schema_of(&code)["definitions"]["A"]["type"],
+        "string"
+    );
+}
+
+#[tokio::test]
+async fn finish_async_timeout() {
+    let stub = Stub::with_script(SLOW_QUICKTYPE);
+    let ctx = stub.context("A").with_timeout(Duration::from_millis(300));
+    let start = Instant::now();
+    match ctx.finish_async(lang()).await {
+        Err(CodegenError::Timeout(timeout)) => assert_eq!(timeout, Duration::from_millis(300)),
+        res => panic!("{:?}", res),
+    }
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    // The `sleep` started by quicktype is gone (or a zombie waiting to be reaped).
+    let pid = std::fs::read_to_string(stub.dir().join("sleep.pid")).unwrap();
+    let stat = format!("/proc/{}/stat", pid.trim());
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while std::fs::read_to_string(&stat).is_ok_and(|stat| !stat.contains(") Z ")) {
+        assert!(Instant::now() < deadline, "quicktype was not killed");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
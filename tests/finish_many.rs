@@ -0,0 +1,30 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Stub;
+use quick_type_schema::*;
+
+#[test]
+fn finish_many_keeps_order() {
+    let stub = Stub::new();
+    let ctx = stub.context("Many").with_parallelism(3);
+    let langs = [
+        Language::Typescript(Default::default()),
+        Language::Rust(Default::default()),
+        Language::Go(Default::default()),
+        Language::Typescript(Default::default()),
+        Language::Swift(Default::default()),
+    ];
+    let results = ctx.finish_many(&langs);
+    assert_eq!(results.len(), langs.len());
+    for (res, lang) in results.into_iter().zip(&langs) {
+        let code = res.unwrap();
+        assert!(
+            code.starts_with(&format!("// {}\n", lang.name())),
+            "{}",
+            code
+        );
+        assert!(code.contains("\"Many\""), "{}", code);
+    }
+}
@@ -0,0 +1,32 @@
+#![cfg(all(unix, feature = "registry", feature = "schemars08"))]
+
+mod common;
+
+use quick_type_schema::QuickTypeExport;
+use schemars::JsonSchema;
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct Shared {
+    x: i32,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema, QuickTypeExport)]
+#[quick_type_schema(group = "a")]
+struct First {
+    shared: Shared,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema, QuickTypeExport)]
+#[quick_type_schema(group = "b")]
+struct Second {
+    shared: Shared,
+    first: First,
+}
+
+#[test]
+fn shared_nested_definitions() {
+    common::check_registry("registry");
+}
@@ -0,0 +1,35 @@
+#![cfg(all(unix, feature = "registry", feature = "schemars1"))]
+
+mod common;
+
+use quick_type_schema::QuickTypeExport;
+use schemars1::JsonSchema;
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[schemars(crate = "schemars1")]
+struct Shared {
+    x: i32,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema, QuickTypeExport)]
+#[schemars(crate = "schemars1")]
+#[quick_type_schema(group = "a", v1)]
+struct First {
+    shared: Shared,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema, QuickTypeExport)]
+#[schemars(crate = "schemars1")]
+#[quick_type_schema(group = "b", v1)]
+struct Second {
+    shared: Shared,
+    first: First,
+}
+
+#[test]
+fn shared_nested_definitions() {
+    common::check_registry("registry_v1");
+}
@@ -0,0 +1,90 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Stub;
+use quick_type_schema::*;
+use serde_json::{json, Value};
+
+/// The schema quicktype was given, without the language line.
+fn finish(ctx: CodegenContext) -> String {
+    let code = ctx
+        .try_finish(Language::Typescript(Default::default()))
+        .unwrap();
+    code.strip_prefix("// typescript\n").unwrap().to_owned()
+}
+
+fn two_types(stub: &Stub, f: impl FnOnce(CodegenContext) -> CodegenContext) -> CodegenContext {
+    let mut ctx = f(stub.context("A"));
+    ctx.add_schema(r#"{ "title": "B", "type": "integer" }"#);
+    ctx
+}
+
+#[test]
+fn synthetic() {
+    let stub = Stub::new();
+    let schema: Value = serde_json::from_str(&finish(two_types(&stub, |ctx| ctx))).unwrap();
+    assert_eq!(
+        schema["properties"],
+        json!({
+            "t0": { "$ref": "#/definitions/A" },
+            "t1": { "$ref": "#/definitions/B" },
+        })
+    );
+    assert_eq!(schema["definitions"]["B"]["type"], "integer");
+}
+
+#[test]
+fn titled_with_converters() {
+    let stub = Stub::new();
+    let ctx = two_types(&stub, |ctx| {
+        ctx.with_top_level(TopLevel::Titled).with_converters(&["B"])
+    });
+    let schema: Value = serde_json::from_str(&finish(ctx)).unwrap();
+    assert_eq!(
+        schema["properties"],
+        json!({ "B": { "$ref": "#/definitions/B" } })
+    );
+    assert_eq!(schema["definitions"]["A"]["type"], "string");
+}
+
+#[test]
+fn omit() {
+    let stub = Stub::new();
+    let ctx = two_types(&stub, |ctx| {
+        ctx.with_top_level(TopLevel::Omit)
+            .with_draft(Draft::Draft2020_12)
+    });
+    assert_eq!(
+        finish(ctx),
+        concat!(
+            "A.schema {\"$ref\":\"../schema.json#/$defs/A\"}\n",
+            "B.schema {\"$ref\":\"../schema.json#/$defs/B\"}\n",
+        )
+    );
+}
+
+#[test]
+fn omit_with_converters() {
+    let stub = Stub::new();
+    let ctx = two_types(&stub, |ctx| {
+        ctx.with_top_level(TopLevel::Omit).with_converters(&["A"])
+    });
+    assert_eq!(
+        finish(ctx),
+        "A.schema {\"$ref\":\"../schema.json#/definitions/A\"}\n"
+    );
+}
+
+#[test]
+fn unknown_converter() {
+    let stub = Stub::new();
+    let ctx = two_types(&stub, |ctx| {
+        ctx.with_top_level(TopLevel::Titled)
+            .with_converters(&["A", "Missing"])
+    });
+    match ctx.try_finish(Language::Typescript(Default::default())) {
+        Err(CodegenError::UnknownConverters(names)) => assert_eq!(names, ["Missing"]),
+        res => panic!("{:?}", res),
+    }
+}